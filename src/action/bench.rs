@@ -0,0 +1,209 @@
+use crate::{
+    auth::{Auth, Credentials},
+    bench::{self, Report, Sample},
+    client::{ClientBuilder, Connector, Proxies, Timeout},
+    env::Variables,
+    Ctx, QuartzResult,
+};
+use futures_util::future::join_all;
+use hyper::{body::HttpBody, Body, Client, Request};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Endpoint to benchmark instead of the one in use
+    handle: Option<String>,
+
+    /// Change a variable when sending the requests
+    #[arg(long = "var", short = 'v', value_name = "KEY=VALUE")]
+    variables: Vec<String>,
+
+    /// Change variables with the ones in a dotenv file. Variables given with --var take
+    /// precedence
+    #[arg(long = "env-file", value_name = "FILE")]
+    env_files: Vec<PathBuf>,
+
+    /// Number of requests to send
+    #[arg(
+        long = "requests",
+        short = 'n',
+        value_name = "N",
+        default_value_t = 100,
+        conflicts_with = "duration"
+    )]
+    requests: usize,
+
+    /// Keep sending requests for this long, like "30s" or "2m", instead of a fixed number
+    #[arg(long, short = 'd', value_name = "DURATION", value_parser = bench::parse_duration)]
+    duration: Option<Duration>,
+
+    /// Maximum number of requests sent at the same time
+    #[arg(
+        long,
+        short = 'c',
+        value_name = "N",
+        default_value_t = 10,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    concurrency: usize,
+
+    /// Print the report as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+pub async fn cmd(ctx: &mut Ctx, args: Args) -> QuartzResult {
+    let handle = match &args.handle {
+        Some(handle) => ctx.require_input_handle(handle),
+        None => ctx.require_handle(),
+    };
+
+    let mut endpoint = ctx.require_endpoint_from_handle(&handle);
+    let mut env = ctx.require_env();
+    env.unlock_secrets()?;
+    let overrides = Variables::overrides(&args.env_files, &args.variables);
+    let env = env.scoped(&endpoint, &overrides);
+
+    if endpoint.grpc.is_some() {
+        panic!("gRPC endpoints cannot be benchmarked");
+    }
+
+    if !endpoint.headers.contains_key("user-agent") {
+        endpoint
+            .headers
+            .insert("user-agent".to_string(), Ctx::user_agent());
+    }
+
+    let timeout = endpoint.timeout.clone().or(ctx.config.timeout.clone());
+    let proxies = Proxies::new(ctx.config.preferences.proxy())?;
+    let version = endpoint.version.unwrap_or_default();
+
+    let client = ClientBuilder::default()
+        .version(version)
+        .proxies(proxies.clone())
+        .tls(env.config.tls.clone().relative_to(ctx.project_dir()))
+        .route(env.config.route.clone().relative_to(ctx.project_dir()))
+        .connect_timeout(timeout.connect())
+        .build()?;
+
+    // Tokens are fetched once, so they don't count towards latency
+    let credentials = Credentials::resolve(&endpoint, &env, ctx, &client).await?;
+    if let Some(Auth::Digest { .. }) = credentials.auth {
+        panic!("digest authentication cannot be benchmarked");
+    }
+
+    // Files are read once, leaving only variables to be rendered for each request
+    endpoint.resolve_url();
+    endpoint.resolve_headers();
+    if endpoint.body().is_none() {
+        endpoint.body = Some(String::new());
+    }
+    if endpoint.form().is_none() {
+        endpoint.form = Some(Default::default());
+    }
+
+    let build = || -> QuartzResult<Request<Body>> {
+        // Built-in variables take a new value for each request
+        let mut endpoint = endpoint.clone();
+        endpoint.apply_variables(&env.variables);
+
+        let mut req = endpoint
+            .into_raw_request()
+            .unwrap_or_else(|_| panic!("malformed request"));
+
+        *req.version_mut() = version.request_version();
+        proxies.authorize(&mut req);
+        env.headers.apply(&mut req)?;
+
+        // Signatures depend on the time they are made
+        credentials.apply(&mut req)?;
+
+        Ok(req.map(Body::from))
+    };
+
+    let deadline = args.duration.map(|duration| Instant::now() + duration);
+    let sent = AtomicUsize::new(0);
+
+    let worker = || async {
+        let mut samples = Vec::new();
+
+        loop {
+            let next = sent.fetch_add(1, Ordering::Relaxed);
+            let done = match deadline {
+                Some(deadline) => Instant::now() >= deadline,
+                None => next >= args.requests,
+            };
+
+            if done {
+                break;
+            }
+
+            // Rendering the request does not count towards latency
+            let req = build();
+
+            let start = Instant::now();
+            let outcome = Timeout::limit(
+                timeout.total(),
+                "operation",
+                self::request(&client, req, &timeout),
+            )
+            .await
+            .map_err(|err| err.to_string());
+
+            samples.push(Sample {
+                latency: start.elapsed(),
+                outcome,
+            });
+        }
+
+        samples
+    };
+
+    let start = Instant::now();
+    let samples: Vec<Sample> = join_all((0..args.concurrency).map(|_| worker()))
+        .await
+        .into_iter()
+        .flatten()
+        .collect();
+
+    let report = Report::new(&samples, start.elapsed());
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print!("{report}");
+    }
+
+    if !report.errors.is_empty() {
+        ctx.code(ExitCode::FAILURE);
+    }
+
+    Ok(())
+}
+
+/// Sends a request and reads its whole response, returning its status code.
+async fn request(
+    client: &Client<Connector>,
+    req: QuartzResult<Request<Body>>,
+    timeout: &Timeout,
+) -> QuartzResult<u16> {
+    let res = Timeout::limit(timeout.read(), "response", async {
+        Ok(client.request(req?).await?)
+    })
+    .await?;
+
+    let status = res.status().as_u16();
+    let mut body = res.into_body();
+
+    while Timeout::limit(timeout.read(), "response", async {
+        Ok(body.data().await.transpose()?)
+    })
+    .await?
+    .is_some()
+    {}
+
+    Ok(status)
+}
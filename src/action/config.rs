@@ -26,7 +26,19 @@ pub fn get(ctx: &Ctx, args: GetArgs) {
     let value = match args.key.as_str() {
         "preferences.editor" => ctx.config.preferences.editor(),
         "preferences.pager" => ctx.config.preferences.pager(),
+        "preferences.proxy" => ctx
+            .config
+            .preferences
+            .proxy()
+            .unwrap_or_default()
+            .to_string(),
+        "preferences.redact" => ctx.config.preferences.redact().join(","),
         "ui.colors" => ctx.config.ui.colors().to_string(),
+        "timeout.connect" => optional(ctx.config.timeout.connect),
+        "timeout.read" => optional(ctx.config.timeout.read),
+        "timeout.total" => optional(ctx.config.timeout.total),
+        "retry.count" => optional(ctx.config.retry.count),
+        "retry.backoff" => optional(ctx.config.retry.backoff),
         _ => panic!("invalid key"),
     };
 
@@ -43,10 +55,17 @@ pub fn set(ctx: &mut Ctx, args: SetArgs) {
     match args.key.as_str() {
         "preferences.editor" => ctx.config.preferences.set_editor(args.value),
         "preferences.pager" => ctx.config.preferences.set_pager(args.value),
+        "preferences.proxy" => ctx.config.preferences.set_proxy(args.value),
+        "preferences.redact" => ctx.config.preferences.set_redact(&args.value),
         "ui.colors" => ctx
             .config
             .ui
             .set_colors(matches!(args.value.as_str(), "true")),
+        "timeout.connect" => ctx.config.timeout.connect = Some(number(&args.value)),
+        "timeout.read" => ctx.config.timeout.read = Some(number(&args.value)),
+        "timeout.total" => ctx.config.timeout.total = Some(number(&args.value)),
+        "retry.count" => ctx.config.retry.count = Some(number(&args.value)),
+        "retry.backoff" => ctx.config.retry.backoff = Some(number(&args.value)),
         _ => panic!("invalid key"),
     };
 
@@ -55,6 +74,16 @@ pub fn set(ctx: &mut Ctx, args: SetArgs) {
     }
 }
 
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+fn number<T: std::str::FromStr>(value: &str) -> T {
+    value
+        .parse()
+        .unwrap_or_else(|_| panic!("invalid value: {value}. Expected a number"))
+}
+
 pub fn ls(ctx: &Ctx) {
     let content = toml::to_string(&ctx.config)
         .unwrap_or_else(|_| panic!("could not parse configuration file"));
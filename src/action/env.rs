@@ -1,15 +1,24 @@
 use core::panic;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
 use std::process::ExitCode;
 
 use crate::{
-    cli::{EnvCmd as Cmd, HeaderCmd},
-    Ctx, Env, PairMap, QuartzResult, StateField,
+    cli::{EnvCmd as Cmd, EnvHeaderCmd as HeaderCmd},
+    dotenv,
+    endpoint::Headers,
+    env::{EnvConfig, Variables},
+    validator, Ctx, Env, PairMap, QuartzResult, StateField,
 };
 use colored::Colorize;
 
 #[derive(clap::Args, Debug)]
 pub struct CreateArgs {
     name: String,
+
+    /// Inherit variables and headers from another environment
+    #[arg(long, value_name = "ENV")]
+    extends: Option<String>,
 }
 
 #[derive(clap::Args, Debug)]
@@ -28,13 +37,48 @@ pub struct RmArgs {
     env: String,
 }
 
+#[derive(clap::Args, Debug)]
+pub struct ImportArgs {
+    /// Environment to import variables into. It is created if missing
+    env: String,
+
+    /// Dotenv file to read variables from
+    #[arg(long, value_name = "FILE")]
+    from: PathBuf,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ExportArgs {
+    /// Environment to be exported. Defaults to the one in use
+    env: Option<String>,
+
+    #[arg(long, value_enum, default_value_t = ExportFormat::Dotenv)]
+    format: ExportFormat,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum ExportFormat {
+    Dotenv,
+    Json,
+    Toml,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct EditArgs {
+    /// Environment to be edited. Defaults to the one in use
+    env: Option<String>,
+}
+
 pub fn cmd(ctx: &mut Ctx, command: Cmd) -> QuartzResult {
     match command {
-        Cmd::Create(args) => create(ctx, args),
+        Cmd::Create(args) => create(ctx, args)?,
         Cmd::Cp(args) => cp(ctx, args)?,
         Cmd::Use(args) => switch(ctx, args)?,
         Cmd::Ls => ls(ctx),
         Cmd::Rm(args) => rm(ctx, args),
+        Cmd::Edit(args) => edit(ctx, args)?,
+        Cmd::Import(args) => import(ctx, args)?,
+        Cmd::Export(args) => export(ctx, args)?,
         Cmd::Header { command } => match command {
             HeaderCmd::Set { header } => header_set(ctx, header)?,
             HeaderCmd::Ls => header_ls(ctx)?,
@@ -46,16 +90,31 @@ pub fn cmd(ctx: &mut Ctx, command: Cmd) -> QuartzResult {
     Ok(())
 }
 
-pub fn create(ctx: &Ctx, args: CreateArgs) {
-    let env = Env::new(&args.name);
+pub fn create(ctx: &Ctx, args: CreateArgs) -> QuartzResult {
+    let mut env = Env::new(&args.name);
 
     if env.exists(ctx) {
         panic!("a environment named {} already exists", args.name.red());
     }
 
+    if let Some(extends) = &args.extends {
+        if !Env::new(extends).exists(ctx) {
+            panic!("environment {} does not exist", extends.red());
+        }
+    }
+
     if env.write(ctx).is_err() {
         panic!("failed to create {} environment", args.name);
     }
+
+    if args.extends.is_some() {
+        env.config.extends = args.extends;
+
+        let config = toml::to_string(&env.config)?;
+        std::fs::write(env.dir(ctx).join(EnvConfig::FILENAME), config)?;
+    }
+
+    Ok(())
 }
 
 pub fn cp(ctx: &Ctx, args: CpArgs) -> QuartzResult {
@@ -67,6 +126,9 @@ pub fn cp(ctx: &Ctx, args: CpArgs) -> QuartzResult {
     for (key, value) in src.variables.iter() {
         dest.variables.insert(key.to_string(), value.to_string());
     }
+    for (key, value) in src.headers.iter() {
+        dest.headers.insert(key.to_string(), value.to_string());
+    }
 
     if dest.exists(ctx) {
         dest.update(ctx)?;
@@ -87,8 +149,9 @@ pub fn switch(ctx: &mut Ctx, args: SwitchArgs) -> QuartzResult {
                 ctx,
                 CreateArgs {
                     name: env.name.clone(),
+                    extends: None,
                 },
-            );
+            )?;
         } else {
             ctx.code(ExitCode::FAILURE);
             return Ok(());
@@ -131,6 +194,19 @@ pub fn rm(ctx: &Ctx, args: RmArgs) {
         panic!("environment {} does not exist", env.name.red());
     }
 
+    let dependents = extended_by(ctx, &env.name);
+    if !dependents.is_empty() {
+        panic!(
+            "environment {} is extended by {}. Change their extends setting first",
+            env.name.red(),
+            dependents.join(", ")
+        );
+    }
+
+    if env.local_dir(ctx).exists() {
+        std::fs::remove_dir_all(env.local_dir(ctx)).ok();
+    }
+
     if std::fs::remove_dir_all(env.dir(ctx)).is_ok() {
         println!("Deleted {} environment", env.name.green());
     } else {
@@ -138,6 +214,96 @@ pub fn rm(ctx: &Ctx, args: RmArgs) {
     }
 }
 
+/// Names of the environments that extend `name` directly, sorted.
+fn extended_by(ctx: &Ctx, name: &str) -> Vec<String> {
+    let mut names = Vec::new();
+
+    if let Ok(entries) = std::fs::read_dir(ctx.path().join("env")) {
+        for entry in entries.flatten() {
+            let Ok(contents) = std::fs::read_to_string(entry.path().join(EnvConfig::FILENAME))
+            else {
+                continue;
+            };
+
+            let extends = toml::from_str::<EnvConfig>(&contents)
+                .ok()
+                .and_then(|config| config.extends);
+            if extends.as_deref() == Some(name) {
+                names.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+    }
+
+    names.sort();
+    names
+}
+
+pub fn edit(ctx: &Ctx, args: EditArgs) -> QuartzResult {
+    let env = match args.env {
+        Some(name) => Env::new(&name),
+        None => ctx.require_env(),
+    };
+
+    if !env.exists(ctx) {
+        panic!("environment {} does not exist", env.name.red());
+    }
+
+    ctx.edit(
+        &env.dir(ctx).join(EnvConfig::FILENAME),
+        validator::toml_as::<EnvConfig>,
+    )?;
+
+    Ok(())
+}
+
+pub fn import(ctx: &Ctx, args: ImportArgs) -> QuartzResult {
+    let variables = Variables::from_file(&args.from)
+        .unwrap_or_else(|err| panic!("failed to read {}: {err}", args.from.display()));
+
+    let mut env = Env::parse(ctx, &args.env)?;
+    for (key, value) in variables.0 {
+        env.own_variable(&key);
+        env.variables.insert(key, value);
+    }
+
+    if env.exists(ctx) {
+        env.update(ctx)?;
+    } else {
+        env.write(ctx)?;
+    }
+
+    Ok(())
+}
+
+/// Prints variables of an environment, including inherited and local ones. Secrets are left
+/// out.
+pub fn export(ctx: &Ctx, args: ExportArgs) -> QuartzResult {
+    let env = match args.env {
+        Some(name) => {
+            if !Env::new(&name).exists(ctx) {
+                panic!("environment {} does not exist", name.red());
+            }
+
+            Env::parse(ctx, &name)?
+        }
+        None => ctx.require_env(),
+    };
+
+    let variables: BTreeMap<&String, &String> = env.variables.iter().collect();
+
+    match args.format {
+        ExportFormat::Dotenv => {
+            for (key, value) in variables {
+                println!("{key}={}", dotenv::quote(value));
+            }
+        }
+        ExportFormat::Json => println!("{}", serde_json::to_string_pretty(&variables)?),
+        ExportFormat::Toml => print!("{}", toml::to_string(&variables)?),
+    }
+
+    Ok(())
+}
+
 pub fn print(ctx: &Ctx) {
     println!(
         "{}",
@@ -150,6 +316,10 @@ pub fn header_set(ctx: &Ctx, args: Vec<String>) -> QuartzResult {
     let mut env = ctx.require_env();
     for header in args {
         env.headers.set(&header);
+
+        if let Some((key, _)) = Headers::pair(&header) {
+            env.own_header(&key);
+        }
     }
     env.update(ctx)?;
     Ok(())
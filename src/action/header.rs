@@ -1,12 +1,30 @@
-use crate::{cli::HeaderCmd as Cmd, Ctx, PairMap, QuartzResult};
+use crate::{cli::HeaderCmd as Cmd, endpoint::Scope, Ctx, PairMap, QuartzResult};
 use std::process::ExitCode;
 
 pub fn cmd(ctx: &mut Ctx, command: Cmd) -> QuartzResult {
     match command {
-        Cmd::Get { key } => get(ctx, key),
-        Cmd::Set { header } => set(ctx, header),
-        Cmd::Rm { key } => rm(ctx, key),
-        Cmd::Ls => ls(ctx),
+        Cmd::Get { key, scope: None } => get(ctx, key),
+        Cmd::Set {
+            header,
+            scope: None,
+        } => set(ctx, header),
+        Cmd::Rm { key, scope: None } => rm(ctx, key),
+        Cmd::Ls { scope: None } => ls(ctx),
+        Cmd::Get {
+            key,
+            scope: Some(handle),
+        } => scope_get(ctx, &handle, key),
+        Cmd::Set {
+            header,
+            scope: Some(handle),
+        } => scope_set(ctx, &handle, header),
+        Cmd::Rm {
+            key,
+            scope: Some(handle),
+        } => scope_rm(ctx, &handle, key),
+        Cmd::Ls {
+            scope: Some(handle),
+        } => scope_ls(ctx, &handle),
     }
 }
 
@@ -55,3 +73,48 @@ pub fn ls(ctx: &Ctx) -> QuartzResult {
     print!("{}", endpoint.headers);
     Ok(())
 }
+
+pub fn scope_get(ctx: &Ctx, handle: &str, key: String) -> QuartzResult {
+    let scope = Scope::parse(&ctx.require_input_handle(handle).dir(ctx));
+    let value = scope
+        .headers
+        .get(&key)
+        .unwrap_or_else(|| panic!("no header named {} found", key));
+
+    println!("{}", value);
+    Ok(())
+}
+
+pub fn scope_set(ctx: &Ctx, handle: &str, headers: Vec<String>) -> QuartzResult {
+    let dir = ctx.require_input_handle(handle).dir(ctx);
+    let mut scope = Scope::parse(&dir);
+
+    for input in headers {
+        scope.headers.set(&input);
+    }
+
+    scope.write(&dir)
+}
+
+pub fn scope_rm(ctx: &mut Ctx, handle: &str, keys: Vec<String>) -> QuartzResult {
+    let dir = ctx.require_input_handle(handle).dir(ctx);
+    let mut scope = Scope::parse(&dir);
+
+    for k in keys {
+        if scope.headers.remove(&k).is_some() {
+            println!("Removed header: {}", k);
+        } else {
+            ctx.code(ExitCode::FAILURE);
+            eprintln!("{}: No such header", k);
+        }
+    }
+
+    scope.write(&dir)
+}
+
+pub fn scope_ls(ctx: &Ctx, handle: &str) -> QuartzResult {
+    let scope = Scope::parse(&ctx.require_input_handle(handle).dir(ctx));
+
+    print!("{}", scope.headers);
+    Ok(())
+}
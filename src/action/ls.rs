@@ -170,7 +170,7 @@ pub fn cmd(ctx: &Ctx, args: Args) {
         }
 
         for child in node.children.iter() {
-            queue.push(&child);
+            queue.push(child);
         }
     }
 
@@ -2,6 +2,7 @@ use crate::action;
 use crate::QuartzResult;
 use crate::{cli::Cmd, Ctx};
 
+pub mod bench;
 pub mod body;
 pub mod config;
 pub mod cookie;
@@ -13,6 +14,7 @@ pub mod init;
 pub mod last;
 pub mod ls;
 pub mod query;
+pub mod secret;
 pub mod send;
 pub mod show;
 pub mod snippet;
@@ -22,7 +24,8 @@ pub async fn cmd(ctx: &mut Ctx, command: Cmd) -> QuartzResult {
     match command {
         Cmd::Init(_) => (), // Init is only run on main, before ctx is resolved
 
-        Cmd::Send(args) => action::send::cmd(ctx, args).await?,
+        Cmd::Send(args) => action::send::cmd(ctx, *args).await?,
+        Cmd::Bench(args) => action::bench::cmd(ctx, args).await?,
         Cmd::Create(args) => action::handle::create(ctx, args),
         Cmd::Use(args) => action::handle::switch(ctx, args),
         Cmd::Ls(args) => action::ls::cmd(ctx, args),
@@ -37,6 +40,7 @@ pub async fn cmd(ctx: &mut Ctx, command: Cmd) -> QuartzResult {
         Cmd::History(args) => action::history::cmd(ctx, args)?,
         Cmd::Last { command } => action::last::cmd(ctx, command)?,
         Cmd::Var { command } => action::var::cmd(ctx, command)?,
+        Cmd::Secret { command } => action::secret::cmd(ctx, command)?,
         Cmd::Env { command } => action::env::cmd(ctx, command)?,
         Cmd::Config { command } => action::config::cmd(ctx, command)?,
     };
@@ -0,0 +1,95 @@
+use crate::{cli::SecretCmd as Cmd, Ctx, QuartzResult};
+use std::io::Read;
+use std::process::ExitCode;
+
+#[derive(clap::Args, Debug)]
+pub struct GetArgs {
+    key: String,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct SetArgs {
+    /// Secret to store. Without a value, it is read from standard input
+    #[arg(name = "KEY[=VALUE]")]
+    secret: String,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct RmArgs {
+    #[arg(name = "KEY", required = true)]
+    keys: Vec<String>,
+}
+
+pub fn cmd(ctx: &mut Ctx, command: Cmd) -> QuartzResult {
+    match command {
+        Cmd::Get(args) => get(ctx, args)?,
+        Cmd::Set(args) => set(ctx, args)?,
+        Cmd::Rm(args) => rm(ctx, args)?,
+        Cmd::Ls => ls(ctx),
+    };
+
+    Ok(())
+}
+
+pub fn get(ctx: &Ctx, args: GetArgs) -> QuartzResult {
+    let mut env = ctx.require_env();
+
+    if !env.secrets.contains(&args.key) {
+        panic!("{} secret not set", args.key);
+    }
+
+    let key = env.secrets.key()?;
+    let value = env.secrets.get(&key, &args.key)?.unwrap_or_default();
+
+    println!("{}", value);
+    Ok(())
+}
+
+pub fn set(ctx: &Ctx, args: SetArgs) -> QuartzResult {
+    let mut env = ctx.require_env();
+
+    let (name, value) = match args.secret.split_once('=') {
+        Some((name, value)) => (name.to_string(), value.to_string()),
+        None => {
+            let mut value = String::new();
+            std::io::stdin().read_to_string(&mut value)?;
+
+            (
+                args.secret,
+                value.trim_end_matches(['\n', '\r']).to_string(),
+            )
+        }
+    };
+
+    if name.is_empty() {
+        panic!("malformed secret. Expected <key>=<value>");
+    }
+
+    let key = env.secrets.key()?;
+    env.secrets.set(&key, &name, &value)?;
+
+    env.update(ctx)?;
+    Ok(())
+}
+
+pub fn ls(ctx: &Ctx) {
+    let env = ctx.require_env();
+
+    for name in env.secrets.names() {
+        println!("{}", name);
+    }
+}
+
+pub fn rm(ctx: &mut Ctx, args: RmArgs) -> QuartzResult {
+    let mut env = ctx.require_env();
+
+    for key in args.keys {
+        if !env.secrets.remove(&key) {
+            ctx.code(ExitCode::FAILURE);
+            eprintln!("{}: No such secret", key);
+        }
+    }
+
+    env.update(ctx)?;
+    Ok(())
+}
@@ -1,25 +1,64 @@
 use crate::{
+    auth::Credentials,
+    client::{ClientBuilder, Connector, HttpVersion, Proxies, Retry, Route, Timeout, Tls},
+    compression::{self, Encoding},
     cookie::CookieJar,
-    endpoint::EndpointPatch,
+    endpoint::{Endpoint, EndpointHandle, EndpointPatch},
+    env::{Env, Variables},
+    grpc::Grpc,
     history::{self, History},
-    Ctx, PairMap, QuartzResult,
+    redact::Redactor,
+    redirect, Ctx, QuartzResult,
 };
 use chrono::Utc;
+use colored::Colorize;
+use futures_util::{stream, FutureExt as _, StreamExt as _};
 use hyper::{
     body::{Bytes, HttpBody},
-    header::{HeaderName, HeaderValue},
-    Body, Client, Uri,
+    header::HeaderValue,
+    Body, Client, HeaderMap, Request, Response, StatusCode, Uri, Version,
 };
+use std::collections::VecDeque;
+use std::panic::AssertUnwindSafe;
 use std::path::{Path, PathBuf};
-use std::str::FromStr;
+use std::process::ExitCode;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use tokio::io::{stdout, AsyncWriteExt as _};
 
 #[derive(clap::Args, Debug)]
 pub struct Args {
+    /// Endpoints to send instead of the one in use. Sending many prints a summary
+    #[arg(value_name = "HANDLE")]
+    handles: Vec<String>,
+
+    /// Also send every endpoint under the given handles
+    #[arg(long, short = 'r')]
+    recursive: bool,
+
+    /// Ask for values of variables that are not set, instead of failing
+    #[arg(long)]
+    prompt: bool,
+
+    /// Maximum number of requests sent at the same time
+    #[arg(
+        long,
+        short = 'j',
+        value_name = "N",
+        default_value_t = 4,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    jobs: usize,
+
     /// Change a variable when sending the request.
     #[arg(long = "var", short = 'v', value_name = "KEY=VALUE")]
     variables: Vec<String>,
 
+    /// Change variables with the ones in a dotenv file. Variables given with --var take
+    /// precedence
+    #[arg(long = "env-file", value_name = "FILE")]
+    env_files: Vec<PathBuf>,
+
     #[command(flatten)]
     patch: EndpointPatch,
 
@@ -27,6 +66,10 @@ pub struct Args {
     #[arg(long)]
     no_follow: bool,
 
+    /// Maximum number of redirects to follow
+    #[arg(long, value_name = "N", default_value_t = redirect::MAX_REDIRECTS)]
+    max_redirects: usize,
+
     /// Pass cookie data to request header
     #[arg(long = "cookie", short = 'b', value_name = "DATA|FILENAME")]
     cookies: Vec<String>,
@@ -34,137 +77,863 @@ pub struct Args {
     /// Which file to write all cookies after a completed request
     #[arg(long, short = 'c', value_name = "FILE")]
     cookie_jar: Option<PathBuf>,
+
+    #[command(flatten)]
+    version: Option<VersionGroup>,
+
+    /// Use this proxy. Overrides configuration and proxy environment variables
+    #[arg(long, value_name = "[PROTOCOL://][USER:PASSWORD@]HOST[:PORT]")]
+    proxy: Option<String>,
+
+    #[command(flatten)]
+    tls: Tls,
+
+    #[command(flatten)]
+    route: Route,
+
+    /// Request a compressed response, sending Accept-Encoding header
+    #[arg(long)]
+    compressed: bool,
+
+    /// Compress request body with this encoding
+    #[arg(long, value_name = "ENCODING")]
+    compressed_body: Option<Encoding>,
+
+    #[command(flatten)]
+    timeout: Timeout,
+
+    #[command(flatten)]
+    retry: Retry,
 }
 
-pub async fn cmd(ctx: &Ctx, mut args: Args) -> QuartzResult {
-    let (handle, mut endpoint) = ctx.require_endpoint();
-    let mut env = ctx.require_env();
-    for var in args.variables {
-        env.variables.set(&var);
+#[derive(clap::Args, Debug)]
+#[group(multiple = false)]
+pub struct VersionGroup {
+    /// Use HTTP/1.1
+    #[arg(long = "http1.1")]
+    http1_1: bool,
+
+    /// Use HTTP/2 if the server agrees on it through TLS negotiation. Falls back to HTTP/1.1
+    #[arg(long)]
+    http2: bool,
+
+    /// Use HTTP/2 without negotiation
+    #[arg(long)]
+    http2_prior_knowledge: bool,
+}
+
+impl VersionGroup {
+    pub fn value(&self) -> Option<HttpVersion> {
+        if self.http1_1 {
+            Some(HttpVersion::Http11)
+        } else if self.http2 {
+            Some(HttpVersion::Http2)
+        } else if self.http2_prior_knowledge {
+            Some(HttpVersion::Http2PriorKnowledge)
+        } else {
+            None
+        }
     }
+}
+
+pub async fn cmd(ctx: &mut Ctx, args: Args) -> QuartzResult {
+    let mut env = ctx.require_env();
+    let mut redactor = ctx.redactor();
+    redactor.values(env.unlock_secrets()?);
+
+    let overrides = Variables::overrides(&args.env_files, &args.variables);
+    env.variables.extend(overrides.0.clone());
 
-    if !endpoint.headers.contains_key("user-agent") {
-        endpoint
-            .headers
-            .insert("user-agent".to_string(), Ctx::user_agent());
+    let cookie_jar = env.cookie_jar(ctx);
+
+    let cookies = args
+        .cookies
+        .iter()
+        .flat_map(|c| {
+            if c.contains('=') {
+                return vec![c.to_owned()];
+            }
+
+            let path = Path::new(c);
+            if !path.exists() {
+                panic!("no such file: {c}");
+            }
+
+            CookieJar::read(path)
+                .unwrap()
+                .iter()
+                .map(|c| format!("{}={}", c.name(), c.value()))
+                .collect()
+        })
+        .collect();
+
+    let is_bulk = args.recursive || args.handles.len() > 1;
+    let handles = targets(ctx, &args);
+
+    if args.prompt {
+        prompt(ctx, &args, &mut env, &overrides, &handles)?;
     }
 
-    let mut cookie_jar = env.cookie_jar(ctx);
+    let session = Session {
+        ctx,
+        args: &args,
+        env,
+        cookie_jar: Mutex::new(cookie_jar),
+        cookies,
+        overrides,
+        redactor,
+    };
 
-    let extras = args.cookies.iter().flat_map(|c| {
-        if c.contains('=') {
-            return vec![c.to_owned()];
-        }
+    let mut failed = false;
 
-        let path = Path::new(c);
-        if !path.exists() {
-            panic!("no such file: {c}");
+    if is_bulk {
+        let timestemp = Utc::now().timestamp_micros();
+
+        // Consecutive timestemps keep history entries apart and in order
+        let rows: Vec<Row> = stream::iter(handles.iter().enumerate())
+            .map(|(i, handle)| session.row(handle, timestemp + i as i64))
+            .buffered(args.jobs)
+            .collect()
+            .await;
+
+        failed = rows.iter().any(|row| !row.success);
+        print_summary(&rows);
+    } else {
+        let reply = session
+            .send(&handles[0], Utc::now().timestamp_micros())
+            .await
+            .unwrap_or_else(|err| panic!("{err}"));
+
+        let _ = stdout().write_all(&reply.body).await;
+
+        if let Some(error) = reply.error {
+            failed = true;
+            eprintln!("{}: {error}", "error".red().bold());
         }
+    }
 
-        CookieJar::read(path)
-            .unwrap()
+    let cookie_jar = session.cookie_jar.into_inner().unwrap();
+    match &args.cookie_jar {
+        Some(path) => cookie_jar.write_at(path)?,
+        None => cookie_jar.write()?,
+    };
+
+    if failed {
+        ctx.code(ExitCode::FAILURE);
+    }
+
+    Ok(())
+}
+
+/// Handles to be sent: the ones given as arguments or the one in use, expanded to every
+/// endpoint under them when recursive.
+fn targets(ctx: &Ctx, args: &Args) -> Vec<EndpointHandle> {
+    let roots = if args.handles.is_empty() {
+        vec![ctx.require_handle()]
+    } else {
+        args.handles
             .iter()
-            .map(|c| format!("{}={}", c.name(), c.value()))
+            .map(|handle| ctx.require_input_handle(handle))
             .collect()
-    });
+    };
 
-    let cookie_value = cookie_jar
+    if !args.recursive {
+        return roots;
+    }
+
+    let mut list = Vec::new();
+    let mut queue = VecDeque::from(roots);
+
+    while let Some(handle) = queue.pop_front() {
+        let mut children = handle.children(ctx);
+        children.sort_by_key(|child| child.handle());
+
+        if handle.endpoint(ctx).is_some() {
+            list.push(handle);
+        }
+
+        // Depth-first, so endpoints are listed as in `ls`
+        for child in children.into_iter().rev() {
+            queue.push_front(child);
+        }
+    }
+
+    if list.is_empty() {
+        panic!("no endpoints to send");
+    }
+
+    list
+}
+
+/// Asks for values of variables missing in any of `handles`, offering to save them into the
+/// environment.
+fn prompt(
+    ctx: &Ctx,
+    args: &Args,
+    env: &mut Env,
+    overrides: &Variables,
+    handles: &[EndpointHandle],
+) -> QuartzResult {
+    let mut missing: Vec<String> = handles
         .iter()
-        .map(|c| format!("{}={}", c.name(), c.value()))
-        .chain(extras)
-        .collect::<Vec<String>>()
-        .join("; ");
+        .flat_map(|handle| {
+            let mut endpoint = ctx.require_endpoint_from_handle(handle);
+            endpoint.update(&mut args.patch.clone());
+            endpoint.apply_env(&env.scoped(&endpoint, overrides));
+
+            endpoint.unresolved_variables()
+        })
+        // Built-in variables cannot be set
+        .filter(|name| !name.starts_with('$'))
+        .collect();
+
+    missing.sort();
+    missing.dedup();
+
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    let mut values = Vec::new();
+    for name in missing {
+        eprint!("{}: ", name.bold());
+
+        let mut value = String::new();
+        std::io::stdin().read_line(&mut value)?;
 
-    if !cookie_value.is_empty() {
-        endpoint
-            .headers
-            .insert(String::from("Cookie"), cookie_value);
+        let value = value.trim_end_matches(['\n', '\r']).to_string();
+        env.variables.insert(name.clone(), value.clone());
+        values.push((name, value));
     }
 
-    let mut entry = history::Entry::builder();
-    entry
-        .handle(handle.handle())
-        .timestemp(Utc::now().timestamp_micros());
+    if ctx.confirm(&format!("Save to {} environment?", env.name.green())) {
+        // Saved from disk, so variables given with --var are kept out
+        let mut saved = ctx.require_env();
+        saved.variables.extend(values);
+        saved.update(ctx)?;
+    }
 
-    endpoint.update(&mut args.patch);
-    endpoint.apply_env(&env);
+    Ok(())
+}
 
-    let body = endpoint.body().cloned();
+/// Outcome of sending a single endpoint.
+struct Reply {
+    /// Response status, as shown in summaries
+    status: String,
 
-    let mut res: hyper::Response<Body>;
+    /// Whether the server reported success
+    success: bool,
 
-    loop {
-        let mut req = endpoint
-            // TODO: Find a way around this clone
-            .clone()
-            .into_request()
-            .unwrap_or_else(|_| panic!("malformed request"));
-        for (key, val) in env.headers.iter() {
-            if !endpoint.headers.contains_key(key) {
-                req.headers_mut()
-                    .insert(HeaderName::from_str(key)?, HeaderValue::from_str(val)?);
+    /// Decoded response body
+    body: Vec<u8>,
+
+    /// Failure reported by the server besides its status
+    error: Option<String>,
+}
+
+/// Line of the summary printed after sending many endpoints.
+struct Row {
+    handle: String,
+    status: String,
+    success: bool,
+    elapsed: Duration,
+    size: usize,
+}
+
+fn print_summary(rows: &[Row]) {
+    let handle_width = rows.iter().map(|row| row.handle.len()).fold(6, usize::max);
+    let status_width = rows.iter().map(|row| row.status.len()).fold(6, usize::max);
+
+    println!(
+        "{}",
+        format!(
+            "{:<handle_width$}  {:<status_width$}  {:>8}  {:>10}",
+            "HANDLE", "STATUS", "TIME", "SIZE"
+        )
+        .bold()
+    );
+
+    for row in rows {
+        let status = format!("{:<status_width$}", row.status);
+        let status = if row.success {
+            status.green()
+        } else {
+            status.red()
+        };
+
+        println!(
+            "{:<handle_width$}  {status}  {:>8}  {:>10}",
+            row.handle,
+            format!("{}ms", row.elapsed.as_millis()),
+            format!("{} B", row.size),
+        );
+    }
+}
+
+/// State shared by every endpoint sent by a single command.
+struct Session<'a> {
+    ctx: &'a Ctx,
+    args: &'a Args,
+    env: Env,
+    cookie_jar: Mutex<CookieJar>,
+
+    /// Cookies given as arguments, as `name=value` pairs
+    cookies: Vec<String>,
+
+    /// Variables given by --env-file and --var, over the ones scoped to each endpoint
+    overrides: Variables,
+
+    /// Hides sensitive headers and secret values from history
+    redactor: Redactor,
+}
+
+impl Session<'_> {
+    /// Sends `handle`, turning any failure into a summary row.
+    async fn row(&self, handle: &EndpointHandle, timestemp: i64) -> Row {
+        let start = Instant::now();
+        let result = AssertUnwindSafe(self.send(handle, timestemp))
+            .catch_unwind()
+            .await;
+
+        let mut row = Row {
+            handle: handle.handle(),
+            status: String::from("error"),
+            success: false,
+            elapsed: start.elapsed(),
+            size: 0,
+        };
+
+        match result {
+            Ok(Ok(reply)) => {
+                if let Some(error) = reply.error {
+                    eprintln!("{}: {}: {error}", "error".red().bold(), row.handle);
+                }
+
+                row.status = reply.status;
+                row.success = reply.success;
+                row.size = reply.body.len();
             }
+            Ok(Err(err)) => eprintln!("{}: {}: {err}", "error".red().bold(), row.handle),
+            // Panic hook already reported it
+            Err(_) => (),
         }
 
-        entry.message(&req);
-        if let Some(ref body) = body {
-            entry.message_raw(body.to_owned());
-        }
+        row
+    }
 
-        let client = {
-            let https = hyper_tls::HttpsConnector::new();
-            Client::builder().build(https)
-        };
+    async fn send(&self, handle: &EndpointHandle, timestemp: i64) -> QuartzResult<Reply> {
+        let mut endpoint = self.ctx.require_endpoint_from_handle(handle);
 
-        res = client.request(req).await?;
+        if !endpoint.headers.contains_key("user-agent") {
+            endpoint
+                .headers
+                .insert("user-agent".to_string(), Ctx::user_agent());
+        }
 
-        entry.message(&res);
+        let cookie_value = self
+            .cookie_jar
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|c| format!("{}={}", c.name(), c.value()))
+            .chain(self.cookies.iter().cloned())
+            .collect::<Vec<String>>()
+            .join("; ");
+
+        if !cookie_value.is_empty() {
+            endpoint
+                .headers
+                .insert(String::from("Cookie"), cookie_value);
+        }
 
-        if let Some(cookie_header) = res.headers().get("Set-Cookie") {
-            let url = endpoint.full_url()?;
+        let mut entry = history::Entry::builder();
+        entry
+            .handle(handle.handle())
+            .timestemp(timestemp)
+            .redactor(self.redactor.clone());
+
+        endpoint.update(&mut self.args.patch.clone());
+
+        let env = self.env.scoped(&endpoint, &self.overrides);
+        endpoint.apply_env(&env);
+
+        // Built-in variables are not set like the others, so they get their own hints
+        let (builtins, missing): (Vec<String>, Vec<String>) = endpoint
+            .unresolved_variables()
+            .into_iter()
+            .partition(|name| name.starts_with('$'));
+        let (process, unknown): (Vec<String>, Vec<String>) = builtins
+            .into_iter()
+            .partition(|name| name.starts_with("$env."));
+
+        let mut errors = Vec::new();
+        if !missing.is_empty() {
+            errors.push(format!(
+                "unresolved variables: {}. Set them with {} or send with {}",
+                missing.join(", "),
+                "quartz var set".green(),
+                "--prompt".green()
+            ));
+        }
+        if !process.is_empty() {
+            let names: Vec<&str> = process.iter().map(|name| &name[5..]).collect();
+            errors.push(format!(
+                "unset environment variables: {}. Export them or add a default, as in {}",
+                names.join(", "),
+                "{{$env.NAME:-default}}".green()
+            ));
+        }
+        if !unknown.is_empty() {
+            errors.push(format!(
+                "unknown built-in variables: {}",
+                unknown.join(", ")
+            ));
+        }
 
-            cookie_jar.set(url.host().unwrap(), cookie_header.to_str()?);
+        if !errors.is_empty() {
+            panic!("{}", errors.join("\n"));
         }
 
-        if args.no_follow || !res.status().is_redirection() {
-            break;
+        if let Some(grpc) = endpoint.grpc.clone() {
+            return self.grpc(entry, endpoint, &env, &grpc).await;
         }
 
-        if let Some(location) = res.headers().get("Location") {
-            let location = location.to_str()?;
+        self.http(entry, endpoint, &env).await
+    }
+
+    /// Proxies given by --proxy, configuration or environment variables, in this order.
+    fn proxies(&self) -> QuartzResult<Proxies> {
+        Proxies::new(
+            self.args
+                .proxy
+                .as_deref()
+                .or(self.ctx.config.preferences.proxy()),
+        )
+    }
+
+    /// TLS options given as arguments, falling back to `env`'s.
+    fn tls(&self, env: &Env) -> Tls {
+        self.args
+            .tls
+            .clone()
+            .or(env.config.tls.clone().relative_to(self.ctx.project_dir()))
+    }
 
-            if location.starts_with('/') {
-                let url = endpoint.full_url()?;
-                // This is awful
-                endpoint.url = Uri::builder()
-                    .authority(url.authority().unwrap().as_str())
-                    .scheme(url.scheme().unwrap().as_str())
-                    .path_and_query(location)
-                    .build()?
-                    .to_string();
-            } else if Uri::from_str(location).is_ok() {
-                endpoint.url = location.to_string();
+    /// Connection route given as arguments, falling back to `env`'s.
+    fn route(&self, env: &Env) -> Route {
+        self.args
+            .route
+            .clone()
+            .or(env.config.route.clone().relative_to(self.ctx.project_dir()))
+    }
+
+    /// Time limits given as arguments, falling back to `endpoint`'s and configuration's.
+    fn timeout(&self, endpoint: &Endpoint) -> Timeout {
+        self.args
+            .timeout
+            .clone()
+            .or(endpoint.timeout.clone())
+            .or(self.ctx.config.timeout.clone())
+    }
+
+    /// Retry policy given as arguments, falling back to `endpoint`'s and configuration's.
+    fn retry(&self, endpoint: &Endpoint) -> Retry {
+        self.args
+            .retry
+            .clone()
+            .or(endpoint.retry.clone())
+            .or(self.ctx.config.retry.clone())
+    }
+
+    /// Authentication resolved for `endpoint`, with the Authorization header value sent up
+    /// front.
+    async fn http(
+        &self,
+        mut entry: history::EntryBuilder,
+        mut endpoint: Endpoint,
+        env: &Env,
+    ) -> QuartzResult<Reply> {
+        let (ctx, args) = (self.ctx, self.args);
+
+        let body = match endpoint.form() {
+            Some(form) => Some(form.to_string()),
+            None => endpoint.body().cloned(),
+        };
+
+        let version = args
+            .version
+            .as_ref()
+            .and_then(|v| v.value())
+            .or(endpoint.version)
+            .unwrap_or_default();
+
+        let proxies = self.proxies()?;
+
+        let tls = self.tls(env);
+
+        let route = self.route(env);
+
+        let timeout = self.timeout(&endpoint);
+        let retry = self.retry(&endpoint);
+
+        let client = ClientBuilder::default()
+            .version(version)
+            .proxies(proxies.clone())
+            .tls(tls)
+            .route(route)
+            .connect_timeout(timeout.connect())
+            .build()?;
+
+        let mut credentials = Credentials::resolve(&endpoint, env, self.ctx, &client).await?;
+
+        let build_request =
+            |endpoint: &Endpoint, credentials: &Credentials| -> QuartzResult<Request<Vec<u8>>> {
+                let mut req = endpoint
+                    // TODO: Find a way around this clone
+                    .clone()
+                    .into_raw_request()
+                    .unwrap_or_else(|_| panic!("malformed request"));
+
+                *req.version_mut() = version.request_version();
+                proxies.authorize(&mut req);
+
+                env.headers.apply(&mut req)?;
+
+                // Endpoint credentials were already dropped, leaving the environment's
+                if credentials.cross_origin {
+                    req.headers_mut().remove(hyper::header::AUTHORIZATION);
+                    req.headers_mut().remove(hyper::header::COOKIE);
+                }
+
+                if args.compressed && !req.headers().contains_key(hyper::header::ACCEPT_ENCODING) {
+                    req.headers_mut().insert(
+                        hyper::header::ACCEPT_ENCODING,
+                        HeaderValue::from_static(Encoding::ACCEPT),
+                    );
+                }
+
+                if let Some(encoding) = args.compressed_body {
+                    if !req.body().is_empty() {
+                        *req.body_mut() = encoding.encode(req.body())?;
+                        req.headers_mut().insert(
+                            hyper::header::CONTENT_ENCODING,
+                            HeaderValue::from_static(encoding.as_str()),
+                        );
+                    }
+                }
+
+                credentials.apply(&mut req)?;
+
+                Ok(req)
+            };
+
+        let exchange = async {
+            let mut res: Response<Bytes>;
+            let mut body = body;
+            let mut redirects = 0;
+
+            loop {
+                let mut req = build_request(&endpoint, &credentials)?;
+                res = self::request(&client, &req, &timeout, &retry).await?;
+
+                // Only after the response the negotiated version is known
+                *req.version_mut() = res.version();
+
+                entry.message(&req);
+                if let Some(ref body) = body {
+                    entry.message_raw(body.to_owned());
+                }
+
+                entry.message(&res);
+
+                if let Some(cookie_header) = res.headers().get("Set-Cookie") {
+                    let url = endpoint.full_url()?;
+
+                    self.cookie_jar
+                        .lock()
+                        .unwrap()
+                        .set(url.host().unwrap(), cookie_header.to_str()?);
+                }
+
+                // Digest authentication is only known after the server's challenge
+                if res.status() == StatusCode::UNAUTHORIZED && credentials.authorization.is_none() {
+                    let answer = credentials.auth.as_ref().and_then(|auth| {
+                        let uri = req.uri().path_and_query()?.as_str();
+
+                        // Servers may offer many schemes, each in its own header
+                        res.headers()
+                            .get_all(hyper::header::WWW_AUTHENTICATE)
+                            .iter()
+                            .filter_map(|challenge| challenge.to_str().ok())
+                            .find_map(|challenge| {
+                                auth.answer(challenge, req.method().as_str(), uri)
+                            })
+                    });
+
+                    if answer.is_some() {
+                        credentials.authorization = answer;
+                        continue;
+                    }
+                }
+
+                if args.no_follow || !res.status().is_redirection() {
+                    break;
+                }
+
+                let Some(location) = res.headers().get(hyper::header::LOCATION) else {
+                    break;
+                };
+
+                if redirects >= args.max_redirects {
+                    panic!("maximum of {} redirects reached", args.max_redirects);
+                }
+                redirects += 1;
+
+                let location = location.to_str().unwrap_or_default();
+                let url = redirect::resolve(req.uri(), location)
+                    .unwrap_or_else(|| panic!("invalid redirect location: {location}"));
+
+                if !redirect::same_origin(req.uri(), &url) {
+                    credentials.forget();
+                    endpoint
+                        .headers
+                        .retain(|key, _| !Credentials::is_credential(key));
+                }
+
+                if let Some(method) = redirect::method_after(res.status(), req.method()) {
+                    endpoint.method = method.to_string();
+                    endpoint.body = Some(String::new());
+                    endpoint.form = Some(Default::default());
+                    endpoint
+                        .headers
+                        .retain(|key, _| !key.to_ascii_lowercase().starts_with("content-"));
+                    body = None;
+                }
+
+                // Location already carries the whole query
+                endpoint.url = url.to_string();
+                endpoint.query.clear();
             }
+
+            QuartzResult::Ok(res)
         };
+
+        let res = Timeout::limit(timeout.total(), "operation", exchange).await?;
+
+        let (parts, bytes) = res.into_parts();
+
+        // Responses without a body may still tell the encoding the body would have
+        let has_body = !bytes.is_empty()
+            && !endpoint.method.eq_ignore_ascii_case("HEAD")
+            && !matches!(
+                parts.status,
+                StatusCode::NO_CONTENT | StatusCode::NOT_MODIFIED
+            );
+
+        let bytes = match parts.headers.get(hyper::header::CONTENT_ENCODING) {
+            Some(encoding) if has_body => compression::decode(encoding.to_str()?, bytes.to_vec())?,
+            _ => bytes.to_vec(),
+        };
+
+        entry.message_raw(String::from_utf8_lossy(&bytes).into_owned());
+        History::write(ctx, entry.build()?)?;
+
+        Ok(Reply {
+            status: parts.status.to_string(),
+            success: !parts.status.is_client_error() && !parts.status.is_server_error(),
+            body: bytes,
+            error: None,
+        })
     }
 
-    match args.cookie_jar {
-        Some(path) => cookie_jar.write_at(&path)?,
-        None => cookie_jar.write()?,
-    };
+    /// Calls an unary or server streaming gRPC method, replying with each response message as
+    /// JSON.
+    async fn grpc(
+        &self,
+        mut entry: history::EntryBuilder,
+        mut endpoint: Endpoint,
+        env: &Env,
+        grpc: &Grpc,
+    ) -> QuartzResult<Reply> {
+        let ctx = self.ctx;
+        let method = grpc.method_descriptor(ctx.project_dir())?;
+        let input = endpoint.body().cloned().unwrap_or_default();
+
+        let url = endpoint.full_url()?;
+        let uri = Uri::builder()
+            .scheme(url.scheme_str().unwrap_or("http"))
+            .authority(
+                url.authority()
+                    .unwrap_or_else(|| panic!("malformed url: {}", endpoint.url))
+                    .as_str(),
+            )
+            .path_and_query(grpc.path())
+            .build()?;
+
+        let mut builder = hyper::Request::post(uri).version(Version::HTTP_2);
+
+        for (key, val) in endpoint.headers.iter() {
+            builder = builder.header(key, val);
+        }
+
+        let mut req = builder.body(Grpc::encode(&method, &input)?.to_vec())?;
+        env.headers.apply(&mut req)?;
+        req.headers_mut().insert(
+            hyper::header::CONTENT_TYPE,
+            HeaderValue::from_static(Grpc::CONTENT_TYPE),
+        );
+        req.headers_mut()
+            .insert(hyper::header::TE, HeaderValue::from_static("trailers"));
+
+        let proxies = self.proxies()?;
+        proxies.authorize(&mut req);
+
+        let timeout = self.timeout(&endpoint);
+        let retry = self.retry(&endpoint);
+
+        let client = ClientBuilder::default()
+            .version(HttpVersion::Http2PriorKnowledge)
+            .proxies(proxies)
+            .tls(self.tls(env))
+            .route(self.route(env))
+            .connect_timeout(timeout.connect())
+            .build()?;
+
+        Credentials::resolve(&endpoint, env, self.ctx, &client)
+            .await?
+            .apply(&mut req)?;
+
+        entry.message(&req);
+        entry.message_raw(input);
+
+        let res = Timeout::limit(
+            timeout.total(),
+            "operation",
+            self::request(&client, &req, &timeout, &retry),
+        )
+        .await?;
+        entry.message(&res);
 
-    let mut bytes = Bytes::new();
+        let (parts, bytes) = res.into_parts();
+        let trailers = parts
+            .extensions
+            .get::<HeaderMap>()
+            .cloned()
+            .unwrap_or_default();
+        let header = |key: &str| {
+            trailers
+                .get(key)
+                .or_else(|| parts.headers.get(key))
+                .and_then(|v| v.to_str().ok())
+                .map(String::from)
+        };
+
+        let status = header("grpc-status");
+        let message = header("grpc-message").unwrap_or_default();
+
+        let output = Grpc::decode(&method, bytes)?.join("\n");
+        entry.message_raw(output.clone());
 
-    while let Some(chunk) = res.data().await {
-        if let Ok(chunk) = chunk {
-            bytes = [bytes, chunk].concat().into();
+        let mut body = output.into_bytes();
+        if !body.is_empty() {
+            body.push(b'\n');
         }
+
+        let (status, error) = match status.as_deref() {
+            Some("0") => (String::from("grpc-status 0"), None),
+            Some(code) => (
+                format!("grpc-status {code}"),
+                Some(format!("grpc-status {code} {message}")),
+            ),
+            None => (
+                parts.status.to_string(),
+                Some(format!("missing grpc-status. HTTP status {}", parts.status)),
+            ),
+        };
+
+        History::write(ctx, entry.build()?)?;
+
+        Ok(Reply {
+            status,
+            success: error.is_none(),
+            body,
+            error,
+        })
     }
+}
 
-    entry.message_raw(String::from_utf8(bytes.to_vec())?);
+/// Sends a copy of `req` and reads its whole response, sending it again according to `retry`
+/// policy. Trailers, if any, are kept as a [`HeaderMap`] in the response extensions.
+async fn request(
+    client: &Client<Connector>,
+    req: &Request<Vec<u8>>,
+    timeout: &Timeout,
+    retry: &Retry,
+) -> QuartzResult<Response<Bytes>> {
+    let mut attempt = 0;
 
-    let _ = stdout().write_all(&bytes).await;
-    History::write(ctx, entry.build()?)?;
+    loop {
+        let result: QuartzResult<Response<Bytes>> = async {
+            let res = Timeout::limit(timeout.read(), "response", async {
+                Ok(client.request(copy(req)).await?)
+            })
+            .await?;
+
+            let (mut parts, mut body) = res.into_parts();
+            let mut bytes = Vec::new();
+
+            while let Some(chunk) = Timeout::limit(timeout.read(), "response", async {
+                Ok(body.data().await.transpose()?)
+            })
+            .await?
+            {
+                bytes.extend_from_slice(&chunk);
+            }
 
-    Ok(())
+            let trailers = Timeout::limit(timeout.read(), "response", async {
+                Ok(body.trailers().await?)
+            })
+            .await?;
+
+            if let Some(trailers) = trailers {
+                parts.extensions.insert(trailers);
+            }
+
+            Ok(Response::from_parts(parts, Bytes::from(bytes)))
+        }
+        .await;
+
+        let reason = match &result {
+            Ok(res) if retry.matches_status(res.status()) => res.status().to_string(),
+            Err(err) if retry.matches_error(err.as_ref()) => err.to_string(),
+            _ => return result,
+        };
+
+        if attempt >= retry.count() {
+            return result;
+        }
+
+        let delay = retry.delay(attempt);
+        attempt += 1;
+
+        eprintln!(
+            "{}: {reason}. Retrying in {}s ({attempt}/{})",
+            "warning".yellow().bold(),
+            delay.as_secs_f64(),
+            retry.count()
+        );
+
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Copies `req` into a new request that can be sent.
+fn copy(req: &Request<Vec<u8>>) -> Request<Body> {
+    let mut copy = Request::new(Body::from(req.body().clone()));
+    *copy.method_mut() = req.method().clone();
+    *copy.uri_mut() = req.uri().clone();
+    *copy.version_mut() = req.version();
+    *copy.headers_mut() = req.headers().clone();
+
+    copy
 }
@@ -1,4 +1,7 @@
-use crate::{cli::SnippetCmd as Cmd, endpoint::EndpointPatch, snippet, Ctx, PairMap, QuartzResult};
+use crate::{
+    cli::SnippetCmd as Cmd, endpoint::EndpointPatch, env::Variables, snippet, Ctx, QuartzResult,
+};
+use std::path::PathBuf;
 
 #[derive(clap::Args, Debug)]
 pub struct Args {
@@ -6,6 +9,11 @@ pub struct Args {
     #[arg(long = "var", short = 'v', value_name = "KEY=VALUE")]
     variables: Vec<String>,
 
+    /// Change variables with the ones in a dotenv file. Variables given with --var take
+    /// precedence
+    #[arg(long = "env-file", value_name = "FILE")]
+    env_files: Vec<PathBuf>,
+
     #[command(flatten)]
     patch: EndpointPatch,
 
@@ -16,17 +24,15 @@ pub struct Args {
 pub fn cmd(ctx: &Ctx, mut args: Args) -> QuartzResult {
     let (_, mut endpoint) = ctx.require_endpoint();
     let mut env = ctx.require_env();
-
-    for var in args.variables {
-        env.variables.set(&var);
-    }
+    env.variables = env.masked_variables();
 
     endpoint.update(&mut args.patch);
-    endpoint.apply_env(&env);
+    let overrides = Variables::overrides(&args.env_files, &args.variables);
+    endpoint.apply_env(&env.scoped(&endpoint, &overrides));
 
     match args.command {
-        Cmd::Curl(curl) => curl.print(&mut endpoint)?,
-        Cmd::Http => snippet::Http::print(&mut endpoint)?,
+        Cmd::Curl(curl) => curl.print(&mut endpoint, &ctx.redactor())?,
+        Cmd::Http => snippet::Http::print(&mut endpoint, &ctx.redactor())?,
     };
 
     Ok(())
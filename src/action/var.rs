@@ -1,21 +1,54 @@
-use crate::{cli::VarCmd as Cmd, env::Variables, Ctx, PairMap, QuartzResult};
+use crate::{
+    cli::VarCmd as Cmd,
+    endpoint::Scope,
+    env::{Env, Variables},
+    Ctx, PairMap, QuartzResult,
+};
+use colored::Colorize;
 use std::process::ExitCode;
 
 #[derive(clap::Args, Debug)]
 pub struct GetArgs {
     key: String,
+
+    /// Use variables shared by a handle with its descendants instead of the environment's
+    #[arg(long, value_name = "HANDLE")]
+    scope: Option<String>,
 }
 
 #[derive(clap::Args, Debug)]
 pub struct SetArgs {
     #[arg(name = "VARIABLE", required = true)]
     variables: Vec<String>,
+
+    /// Only set for the current user, overriding the shared value
+    #[arg(long, conflicts_with = "scope")]
+    local: bool,
+
+    /// Share variables with every endpoint under a handle instead of the whole environment
+    #[arg(long, value_name = "HANDLE")]
+    scope: Option<String>,
 }
 
 #[derive(clap::Args, Debug)]
 pub struct RmArgs {
     #[arg(name = "KEY", required = true)]
     keys: Vec<String>,
+
+    /// Remove local overrides of the current user
+    #[arg(long, conflicts_with = "scope")]
+    local: bool,
+
+    /// Remove variables shared by a handle with its descendants
+    #[arg(long, value_name = "HANDLE")]
+    scope: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct LsArgs {
+    /// List variables shared by a handle with its descendants instead of the environment's
+    #[arg(long, value_name = "HANDLE")]
+    scope: Option<String>,
 }
 
 pub fn cmd(ctx: &mut Ctx, command: Cmd) -> QuartzResult {
@@ -24,16 +57,18 @@ pub fn cmd(ctx: &mut Ctx, command: Cmd) -> QuartzResult {
         Cmd::Get(args) => get(ctx, args),
         Cmd::Set(args) => set(ctx, args)?,
         Cmd::Rm(args) => rm(ctx, args)?,
-        Cmd::Ls => ls(ctx),
+        Cmd::Ls(args) => ls(ctx, args),
     };
 
     Ok(())
 }
 
 pub fn get(ctx: &Ctx, args: GetArgs) {
-    let env = ctx.require_env();
-    let v = env
-        .variables
+    let variables = match &args.scope {
+        Some(handle) => Scope::parse(&ctx.require_input_handle(handle).dir(ctx)).variables,
+        None => ctx.require_env().masked_variables(),
+    };
+    let v = variables
         .get(&args.key)
         .unwrap_or_else(|| panic!("{} variable not set", args.key));
 
@@ -41,18 +76,60 @@ pub fn get(ctx: &Ctx, args: GetArgs) {
 }
 
 pub fn set(ctx: &Ctx, args: SetArgs) -> QuartzResult {
+    if let Some(handle) = &args.scope {
+        let dir = ctx.require_input_handle(handle).dir(ctx);
+        let mut scope = Scope::parse(&dir);
+
+        for input in args.variables {
+            scope.variables.set(&input);
+        }
+
+        return scope.write(&dir);
+    }
+
     let mut env = ctx.require_env();
+
+    if args.local {
+        for input in args.variables {
+            env.local.set(&input);
+        }
+
+        env.update_local(ctx)?;
+        return Ok(());
+    }
+
     for input in args.variables {
         env.variables.set(&input);
+
+        if let Some((key, _)) = Variables::pair(&input) {
+            env.own_variable(&key);
+            warn_shadowed(&env, &key);
+        }
     }
 
     env.update(ctx)?;
     Ok(())
 }
 
-pub fn ls(ctx: &Ctx) {
-    let env = ctx.require_env();
-    print!("{}", env.variables);
+/// Warns that `key` keeps its local value, whatever is done to the shared one.
+fn warn_shadowed(env: &Env, key: &str) {
+    if env.local.contains_key(key) {
+        eprintln!(
+            "{}: {key} is shadowed by a local variable. Remove it with {}",
+            "warning".yellow().bold(),
+            format!("quartz var rm --local {key}").green()
+        );
+    }
+}
+
+pub fn ls(ctx: &Ctx, args: LsArgs) {
+    match &args.scope {
+        Some(handle) => print!(
+            "{}",
+            Scope::parse(&ctx.require_input_handle(handle).dir(ctx)).variables
+        ),
+        None => print!("{}", ctx.require_env().masked_variables()),
+    }
 }
 
 pub fn edit(ctx: &Ctx) -> QuartzResult {
@@ -66,14 +143,41 @@ pub fn edit(ctx: &Ctx) -> QuartzResult {
 }
 
 pub fn rm(ctx: &mut Ctx, args: RmArgs) -> QuartzResult {
+    if let Some(handle) = &args.scope {
+        let dir = ctx.require_input_handle(handle).dir(ctx);
+        let mut scope = Scope::parse(&dir);
+
+        for key in args.keys {
+            if scope.variables.remove(&key).is_none() {
+                ctx.code(ExitCode::FAILURE);
+                eprintln!("{}: No such variable", key);
+            }
+        }
+
+        return scope.write(&dir);
+    }
+
     let mut env = ctx.require_env();
 
+    if args.local {
+        for key in args.keys {
+            if env.local.remove(&key).is_none() {
+                ctx.code(ExitCode::FAILURE);
+                eprintln!("{}: No such local variable", key);
+            }
+        }
+
+        env.update_local(ctx)?;
+        return Ok(());
+    }
+
     for key in args.keys {
-        env.variables.remove(&key).unwrap_or_else(|| {
+        if env.variables.remove(&key).is_none() {
             ctx.code(ExitCode::FAILURE);
             eprintln!("{}: No such variable", key);
-            "".to_string()
-        });
+        }
+
+        warn_shadowed(&env, &key);
     }
 
     env.update(ctx)?;
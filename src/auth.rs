@@ -0,0 +1,787 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use hyper::header::HeaderValue;
+use hyper::{Body, Client, Request};
+use openssl::hash::{hash, MessageDigest};
+use openssl::pkey::PKey;
+use openssl::sha::sha256;
+use openssl::sign::Signer;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde::{Deserialize, Serialize};
+
+use crate::client::Connector;
+use crate::endpoint::Endpoint;
+use crate::env::{Env, Variables};
+use crate::form::{Form, FormEncoding};
+use crate::{Ctx, QuartzResult};
+
+/// Credentials used to authorize requests.
+///
+/// # Example
+///
+/// ```toml
+/// [auth]
+/// type = "basic"
+/// user = "{{user}}"
+/// password = "{{password}}"
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Auth {
+    Basic {
+        user: String,
+        #[serde(default)]
+        password: String,
+    },
+
+    Bearer {
+        token: String,
+    },
+
+    /// Answers a `WWW-Authenticate: Digest` challenge after a 401 response
+    Digest {
+        user: String,
+        #[serde(default)]
+        password: String,
+    },
+
+    /// Bearer token fetched from an OAuth 2.0 authorization server
+    OAuth2(OAuth2),
+
+    /// Signs requests for AWS services
+    #[serde(rename = "aws-sigv4")]
+    AwsSigV4(AwsSigV4),
+
+    /// Stops authentication inherited from parents or environment
+    None,
+}
+
+impl Auth {
+    /// Replaces `{{key}}` and built-in notations in credentials.
+    pub fn apply_variables(&mut self, variables: &Variables) {
+        let fields = match self {
+            Auth::Basic { user, password } | Auth::Digest { user, password } => {
+                vec![user, password]
+            }
+            Auth::Bearer { token } => vec![token],
+            Auth::OAuth2(oauth) => vec![
+                &mut oauth.token_url,
+                &mut oauth.client_id,
+                &mut oauth.client_secret,
+                &mut oauth.scope,
+                &mut oauth.username,
+                &mut oauth.password,
+                &mut oauth.refresh_token,
+            ],
+            Auth::AwsSigV4(aws) => vec![
+                &mut aws.access_key,
+                &mut aws.secret_key,
+                &mut aws.session_token,
+                &mut aws.region,
+                &mut aws.service,
+            ],
+            Auth::None => vec![],
+        };
+
+        for field in fields {
+            *field = variables.apply(field);
+        }
+    }
+
+    /// Authorization header value sent up front, without a challenge from the server.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quartz_cli::auth::Auth;
+    ///
+    /// let auth = Auth::Basic { user: "Aladdin".into(), password: "open sesame".into() };
+    /// assert_eq!(auth.header().as_deref(), Some("Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ=="));
+    ///
+    /// let auth = Auth::Bearer { token: "abc".into() };
+    /// assert_eq!(auth.header().as_deref(), Some("Bearer abc"));
+    /// ```
+    pub fn header(&self) -> Option<String> {
+        match self {
+            Auth::Basic { user, password } => {
+                let credentials =
+                    base64::engine::general_purpose::STANDARD.encode(format!("{user}:{password}"));
+
+                Some(format!("Basic {credentials}"))
+            }
+            Auth::Bearer { token } => Some(format!("Bearer {token}")),
+            Auth::Digest { .. } | Auth::OAuth2(_) | Auth::AwsSigV4(_) | Auth::None => None,
+        }
+    }
+
+    /// Authorization header value sent up front, fetching an OAuth 2.0 token when needed.
+    pub async fn authorization(
+        &self,
+        ctx: &Ctx,
+        client: &Client<Connector>,
+    ) -> QuartzResult<Option<String>> {
+        match self {
+            Auth::OAuth2(oauth) => {
+                let token = oauth.token(client, &OAuth2::cache_dir(ctx)).await?;
+
+                Ok(Some(format!("Bearer {token}")))
+            }
+            auth => Ok(auth.header()),
+        }
+    }
+
+    /// Authorization header value answering a `WWW-Authenticate` `challenge` for a request of
+    /// `method` to `uri`, which is the request path and query.
+    ///
+    /// Returns [`None`] if this is not a digest authentication or the challenge is unsupported.
+    pub fn answer(&self, challenge: &str, method: &str, uri: &str) -> Option<String> {
+        let Auth::Digest { user, password } = self else {
+            return None;
+        };
+
+        let mut cnonce = [0; 16];
+        openssl::rand::rand_bytes(&mut cnonce).ok()?;
+
+        DigestChallenge::parse(challenge)?.answer(user, password, method, uri, &hex(&cnonce))
+    }
+}
+
+/// Credentials carried by requests, dropped once a redirect leaves the original origin.
+#[derive(Debug, Default)]
+pub struct Credentials {
+    pub auth: Option<Auth>,
+
+    /// Authorization header value, when known
+    pub authorization: Option<String>,
+
+    pub cross_origin: bool,
+}
+
+impl Credentials {
+    /// Resolves authentication for `endpoint` in `env`, fetching an OAuth 2.0 token when needed.
+    pub async fn resolve(
+        endpoint: &Endpoint,
+        env: &Env,
+        ctx: &Ctx,
+        client: &Client<Connector>,
+    ) -> QuartzResult<Self> {
+        let auth = endpoint.resolve_auth(env);
+        let authorization = match &auth {
+            Some(auth) => auth.authorization(ctx, client).await?,
+            None => None,
+        };
+
+        Ok(Self {
+            auth,
+            authorization,
+            cross_origin: false,
+        })
+    }
+
+    /// Adds the Authorization header to `req`, unless it sets its own, and signs it when
+    /// needed. Signatures cover the final headers and body, so it must come last.
+    pub fn apply(&self, req: &mut Request<Vec<u8>>) -> QuartzResult {
+        if let Some(authorization) = &self.authorization {
+            if !req.headers().contains_key(hyper::header::AUTHORIZATION) {
+                req.headers_mut().insert(
+                    hyper::header::AUTHORIZATION,
+                    HeaderValue::from_str(authorization)?,
+                );
+            }
+        }
+
+        if let Some(Auth::AwsSigV4(aws)) = &self.auth {
+            aws.sign(req, Utc::now())?;
+        }
+
+        Ok(())
+    }
+
+    pub fn forget(&mut self) {
+        self.auth = None;
+        self.authorization = None;
+        self.cross_origin = true;
+    }
+
+    /// Whether a header carries credentials that must not leak to other origins.
+    pub fn is_credential(header: &str) -> bool {
+        header.eq_ignore_ascii_case("authorization") || header.eq_ignore_ascii_case("cookie")
+    }
+}
+
+/// Parameters of a `WWW-Authenticate: Digest` header, as described by RFC 7616.
+#[derive(Debug)]
+pub struct DigestChallenge(HashMap<String, String>);
+
+impl DigestChallenge {
+    /// # Examples
+    ///
+    /// ```
+    /// use quartz_cli::auth::DigestChallenge;
+    ///
+    /// assert!(DigestChallenge::parse(r#"Digest realm="api", nonce="abc""#).is_some());
+    /// assert!(DigestChallenge::parse(r#"Basic realm="api""#).is_none());
+    /// ```
+    pub fn parse(header: &str) -> Option<Self> {
+        let (scheme, mut rest) = header.trim().split_once(' ')?;
+        if !scheme.eq_ignore_ascii_case("digest") {
+            return None;
+        }
+
+        let mut params = HashMap::new();
+
+        loop {
+            rest = rest.trim_start_matches([' ', ',']);
+            let Some((key, value)) = rest.split_once('=') else {
+                break;
+            };
+
+            let (value, remaining) = match value.strip_prefix('"') {
+                Some(quoted) => {
+                    let end = quoted.find('"')?;
+                    (&quoted[..end], &quoted[end + 1..])
+                }
+                None => value.split_at(value.find(',').unwrap_or(value.len())),
+            };
+
+            params.insert(key.trim().to_ascii_lowercase(), value.to_string());
+            rest = remaining;
+        }
+
+        Some(Self(params))
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).map(String::as_str)
+    }
+
+    /// Computes the Authorization header value for these parameters.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quartz_cli::auth::DigestChallenge;
+    ///
+    /// // From RFC 2617
+    /// let challenge = DigestChallenge::parse(
+    ///     r#"Digest realm="testrealm@host.com", qop="auth,auth-int", nonce="dcd98b7102dd2f0e8b11d0f600bfb0c093", opaque="5ccc069c403ebaf9f0171e9517f40e41""#,
+    /// ).unwrap();
+    ///
+    /// let header = challenge
+    ///     .answer("Mufasa", "Circle Of Life", "GET", "/dir/index.html", "0a4f113b")
+    ///     .unwrap();
+    ///
+    /// assert!(header.contains(r#"response="6629fae49393a05397450978507c4ef1""#));
+    /// ```
+    pub fn answer(
+        &self,
+        user: &str,
+        password: &str,
+        method: &str,
+        uri: &str,
+        cnonce: &str,
+    ) -> Option<String> {
+        let realm = self.get("realm").unwrap_or_default();
+        let nonce = self.get("nonce")?;
+        let algorithm = self.get("algorithm").unwrap_or("MD5");
+
+        let digest = match algorithm.to_ascii_uppercase().as_str() {
+            "MD5" | "MD5-SESS" => MessageDigest::md5(),
+            "SHA-256" | "SHA-256-SESS" => MessageDigest::sha256(),
+            _ => return None,
+        };
+        let hash = |input: &str| hash(digest, input.as_bytes()).ok().map(|bytes| hex(&bytes));
+
+        let qop = self
+            .get("qop")
+            .map(|qop| qop.split(',').map(str::trim).any(|q| q == "auth"));
+
+        let mut ha1 = hash(&format!("{user}:{realm}:{password}"))?;
+        if algorithm.to_ascii_uppercase().ends_with("-SESS") {
+            ha1 = hash(&format!("{ha1}:{nonce}:{cnonce}"))?;
+        }
+
+        let ha2 = hash(&format!("{method}:{uri}"))?;
+        let nc = "00000001";
+
+        let response = match qop {
+            Some(true) => hash(&format!("{ha1}:{nonce}:{nc}:{cnonce}:auth:{ha2}"))?,
+            Some(false) => return None,
+            None => hash(&format!("{ha1}:{nonce}:{ha2}"))?,
+        };
+
+        let mut header = format!(
+            "Digest username=\"{user}\", realm=\"{realm}\", nonce=\"{nonce}\", uri=\"{uri}\", algorithm={algorithm}, response=\"{response}\""
+        );
+
+        if qop.is_some() {
+            header.push_str(&format!(", qop=auth, nc={nc}, cnonce=\"{cnonce}\""));
+        }
+
+        if let Some(opaque) = self.get("opaque") {
+            header.push_str(&format!(", opaque=\"{opaque}\""));
+        }
+
+        Some(header)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Grant {
+    ClientCredentials,
+    Password,
+    RefreshToken,
+}
+
+impl Grant {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Grant::ClientCredentials => "client_credentials",
+            Grant::Password => "password",
+            Grant::RefreshToken => "refresh_token",
+        }
+    }
+}
+
+/// OAuth 2.0 grant used to fetch access tokens.
+///
+/// # Example
+///
+/// ```toml
+/// [auth]
+/// type = "oauth2"
+/// grant = "client_credentials"
+/// token_url = "https://auth.example.com/oauth/token"
+/// client_id = "{{client_id}}"
+/// client_secret = "{{client_secret}}"
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OAuth2 {
+    pub grant: Grant,
+    pub token_url: String,
+    pub client_id: String,
+
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub client_secret: String,
+
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub scope: String,
+
+    /// Resource owner name, used by password grant
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub username: String,
+
+    /// Resource owner password, used by password grant
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub password: String,
+
+    /// Used by refresh_token grant
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub refresh_token: String,
+}
+
+/// Access token kept in cache between requests.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Token {
+    /// Identifies the configuration that fetched this token
+    key: String,
+
+    access_token: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    refresh_token: Option<String>,
+
+    /// Unix timestamp, in seconds, after which the token is no longer valid
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires_at: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: Option<String>,
+    expires_in: Option<i64>,
+}
+
+impl Token {
+    /// Seconds before expiration in which a token is already considered expired, so it does not
+    /// expire on its way to the server.
+    const LEEWAY: i64 = 30;
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|at| at - Self::LEEWAY <= chrono::Utc::now().timestamp())
+    }
+}
+
+impl OAuth2 {
+    /// Directory where tokens are cached.
+    pub fn cache_dir(ctx: &Ctx) -> PathBuf {
+        ctx.path().join("user").join("oauth2")
+    }
+
+    fn key(&self) -> String {
+        [
+            self.grant.as_str(),
+            &self.token_url,
+            &self.client_id,
+            &self.scope,
+            &self.username,
+        ]
+        .join(" ")
+    }
+
+    /// Name of the file caching tokens of this configuration, so configurations sharing an
+    /// environment keep their own tokens.
+    fn cache_filename(&self) -> String {
+        let hash = sha256(self.key().as_bytes());
+
+        format!("{}.toml", hex(&hash[..8]))
+    }
+
+    /// Returns an access token, fetching a new one when the token cached in `cache_dir` is
+    /// missing or expired.
+    ///
+    /// # Panics
+    ///
+    /// Program is terminated if the authorization server refuses to issue a token.
+    pub async fn token(
+        &self,
+        client: &Client<Connector>,
+        cache_dir: &Path,
+    ) -> QuartzResult<String> {
+        let cache = cache_dir.join(self.cache_filename());
+        let cached = std::fs::read_to_string(&cache)
+            .ok()
+            .and_then(|content| toml::from_str::<Token>(&content).ok())
+            .filter(|token| token.key == self.key());
+
+        if let Some(token) = &cached {
+            if !token.is_expired() {
+                return Ok(token.access_token.clone());
+            }
+        }
+
+        let refreshed = match cached.and_then(|token| token.refresh_token) {
+            Some(refresh_token) => {
+                let mut oauth = self.clone();
+                oauth.grant = Grant::RefreshToken;
+                oauth.refresh_token = refresh_token;
+
+                oauth.fetch(client).await.ok()
+            }
+            None => None,
+        };
+
+        // A rejected refresh token falls back to the configured grant
+        let token = match refreshed {
+            Some(token) => token,
+            None => self
+                .fetch(client)
+                .await
+                .unwrap_or_else(|err| panic!("failed to fetch OAuth2 token: {err}")),
+        };
+
+        std::fs::create_dir_all(cache_dir)?;
+        std::fs::write(cache, toml::to_string(&token)?)?;
+
+        Ok(token.access_token)
+    }
+
+    /// Requests a new access token from the authorization server.
+    async fn fetch(&self, client: &Client<Connector>) -> QuartzResult<Token> {
+        let mut form = Form::new(FormEncoding::UrlEncoded);
+        form.set(&format!("grant_type={}", self.grant.as_str()));
+
+        let params = [
+            ("client_id", &self.client_id),
+            ("client_secret", &self.client_secret),
+            ("scope", &self.scope),
+        ];
+
+        let grant_params = match self.grant {
+            Grant::ClientCredentials => vec![],
+            Grant::Password => vec![("username", &self.username), ("password", &self.password)],
+            Grant::RefreshToken => vec![("refresh_token", &self.refresh_token)],
+        };
+
+        for (key, value) in params.into_iter().chain(grant_params) {
+            if !value.is_empty() {
+                form.set(&format!("{key}={value}"));
+            }
+        }
+
+        let req = Request::post(&self.token_url)
+            .header(
+                hyper::header::CONTENT_TYPE,
+                "application/x-www-form-urlencoded",
+            )
+            .header(hyper::header::ACCEPT, "application/json")
+            .body(Body::from(form.urlencoded()))?;
+
+        let res = client.request(req).await?;
+        let status = res.status();
+        let bytes = hyper::body::to_bytes(res.into_body()).await?;
+
+        if !status.is_success() {
+            return Err(format!("{status}\n\n{}", String::from_utf8_lossy(&bytes)).into());
+        }
+
+        let response: TokenResponse = serde_json::from_slice(&bytes)?;
+
+        Ok(Token {
+            key: self.key(),
+            access_token: response.access_token,
+            refresh_token: response.refresh_token,
+            expires_at: response
+                .expires_in
+                .map(|seconds| chrono::Utc::now().timestamp() + seconds),
+        })
+    }
+}
+
+/// Characters escaped when building AWS canonical requests: everything but unreserved ones.
+const AWS_ENCODE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// AWS Signature Version 4 credentials. Empty fields fall back to the **AWS_ACCESS_KEY_ID**,
+/// **AWS_SECRET_ACCESS_KEY**, **AWS_SESSION_TOKEN** and **AWS_REGION** environment variables.
+///
+/// # Example
+///
+/// ```toml
+/// [auth]
+/// type = "aws-sigv4"
+/// service = "execute-api"
+/// region = "{{region}}"
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AwsSigV4 {
+    pub service: String,
+
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub region: String,
+
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub access_key: String,
+
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub secret_key: String,
+
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub session_token: String,
+}
+
+impl AwsSigV4 {
+    const ALGORITHM: &'static str = "AWS4-HMAC-SHA256";
+
+    /// Headers that may be changed on the way to the server, so they are never signed.
+    const UNSIGNED_HEADERS: [&'static str; 5] = [
+        "authorization",
+        "proxy-authorization",
+        "connection",
+        "expect",
+        "user-agent",
+    ];
+
+    fn value(field: &str, vars: &[&str]) -> Option<String> {
+        if !field.is_empty() {
+            return Some(field.to_string());
+        }
+
+        vars.iter().find_map(|var| std::env::var(var).ok())
+    }
+
+    /// Adds signature headers to `req`, as if it was sent at `now`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chrono::TimeZone;
+    /// use quartz_cli::auth::AwsSigV4;
+    ///
+    /// // From AWS Signature Version 4 test suite: get-vanilla
+    /// let aws = AwsSigV4 {
+    ///     service: "service".into(),
+    ///     region: "us-east-1".into(),
+    ///     access_key: "AKIDEXAMPLE".into(),
+    ///     secret_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".into(),
+    ///     session_token: "".into(),
+    /// };
+    ///
+    /// let mut req = hyper::Request::get("https://example.amazonaws.com/").body(vec![]).unwrap();
+    /// let now = chrono::Utc.with_ymd_and_hms(2015, 8, 30, 12, 36, 0).unwrap();
+    /// aws.sign(&mut req, now).unwrap();
+    ///
+    /// assert_eq!(
+    ///     req.headers()["authorization"],
+    ///     "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+    ///     SignedHeaders=host;x-amz-date, \
+    ///     Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+    /// );
+    /// ```
+    pub fn sign(&self, req: &mut Request<Vec<u8>>, now: DateTime<Utc>) -> QuartzResult {
+        let access_key = Self::value(&self.access_key, &["AWS_ACCESS_KEY_ID"])
+            .unwrap_or_else(|| panic!("missing AWS access key"));
+        let secret_key = Self::value(&self.secret_key, &["AWS_SECRET_ACCESS_KEY"])
+            .unwrap_or_else(|| panic!("missing AWS secret key"));
+        let region = Self::value(&self.region, &["AWS_REGION", "AWS_DEFAULT_REGION"])
+            .unwrap_or_else(|| panic!("missing AWS region"));
+        let session_token = Self::value(&self.session_token, &["AWS_SESSION_TOKEN"]);
+
+        let timestamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = hex(&sha256(req.body()));
+
+        let authority = req
+            .uri()
+            .authority()
+            .unwrap_or_else(|| panic!("malformed url: {}", req.uri()))
+            .to_string();
+
+        let headers = req.headers_mut();
+        headers.insert(hyper::header::HOST, HeaderValue::from_str(&authority)?);
+        headers.insert("x-amz-date", HeaderValue::from_str(&timestamp)?);
+
+        if let Some(token) = session_token {
+            headers.insert("x-amz-security-token", HeaderValue::from_str(&token)?);
+        }
+
+        if self.service == "s3" {
+            headers.insert(
+                "x-amz-content-sha256",
+                HeaderValue::from_str(&payload_hash)?,
+            );
+        }
+
+        let mut signed = BTreeMap::<String, Vec<String>>::new();
+        for (key, value) in req.headers() {
+            if Self::UNSIGNED_HEADERS.contains(&key.as_str()) {
+                continue;
+            }
+
+            let value = String::from_utf8_lossy(value.as_bytes());
+            signed
+                .entry(key.as_str().to_string())
+                .or_default()
+                .push(value.split_whitespace().collect::<Vec<_>>().join(" "));
+        }
+
+        let canonical_headers: String = signed
+            .iter()
+            .map(|(key, values)| format!("{key}:{}\n", values.join(",")))
+            .collect();
+        let signed_headers = signed.keys().cloned().collect::<Vec<_>>().join(";");
+
+        let canonical_request = [
+            req.method().as_str(),
+            &self.canonical_path(req.uri().path()),
+            &Self::canonical_query(req.uri().query().unwrap_or_default()),
+            &canonical_headers,
+            &signed_headers,
+            &payload_hash,
+        ]
+        .join("\n");
+
+        let scope = format!("{date}/{region}/{}/aws4_request", self.service);
+        let string_to_sign = format!(
+            "{}\n{timestamp}\n{scope}\n{}",
+            Self::ALGORITHM,
+            hex(&sha256(canonical_request.as_bytes()))
+        );
+
+        let mut key = hmac_sha256(format!("AWS4{secret_key}").as_bytes(), date.as_bytes())?;
+        for part in [region.as_str(), &self.service, "aws4_request"] {
+            key = hmac_sha256(&key, part.as_bytes())?;
+        }
+
+        let signature = hex(&hmac_sha256(&key, string_to_sign.as_bytes())?);
+
+        req.headers_mut().insert(
+            hyper::header::AUTHORIZATION,
+            HeaderValue::from_str(&format!(
+                "{} Credential={access_key}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+                Self::ALGORITHM,
+            ))?,
+        );
+
+        Ok(())
+    }
+
+    /// Encodes each path segment. S3 expects it once, while other services expect the already
+    /// encoded path to be encoded again.
+    fn canonical_path(&self, path: &str) -> String {
+        let path = if path.is_empty() { "/" } else { path };
+
+        path.split('/')
+            .map(|segment| {
+                let decoded = percent_decode_str(segment).decode_utf8_lossy();
+                let encoded = utf8_percent_encode(&decoded, AWS_ENCODE).to_string();
+
+                if self.service == "s3" {
+                    encoded
+                } else {
+                    utf8_percent_encode(&encoded, AWS_ENCODE).to_string()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Sorts and encodes query parameters.
+    fn canonical_query(query: &str) -> String {
+        let encode = |input: &str| {
+            let decoded = percent_decode_str(input).decode_utf8_lossy();
+            utf8_percent_encode(&decoded, AWS_ENCODE).to_string()
+        };
+
+        let mut params: Vec<(String, String)> = query
+            .split('&')
+            .filter(|param| !param.is_empty())
+            .map(|param| {
+                let (key, value) = param.split_once('=').unwrap_or((param, ""));
+                (encode(key), encode(value))
+            })
+            .collect();
+
+        params.sort();
+        params
+            .iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+}
+
+/// HMAC-SHA256, as described by RFC 2104.
+///
+/// # Examples
+///
+/// ```
+/// use quartz_cli::auth::hmac_sha256;
+///
+/// // From RFC 4231, test case 2
+/// let mac = hmac_sha256(b"Jefe", b"what do ya want for nothing?").unwrap();
+/// let hex: String = mac.iter().map(|b| format!("{b:02x}")).collect();
+///
+/// assert_eq!(hex, "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+/// ```
+pub fn hmac_sha256(key: &[u8], data: &[u8]) -> QuartzResult<Vec<u8>> {
+    let key = PKey::hmac(key)?;
+    let mut signer = Signer::new(MessageDigest::sha256(), &key)?;
+    signer.update(data)?;
+
+    Ok(signer.sign_to_vec()?)
+}
+
+/// Lowercase hexadecimal representation of `bytes`.
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
@@ -0,0 +1,190 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::time::Duration;
+
+use serde::Serialize;
+
+/// Parses a duration such as `30s`, `500ms`, `2m` or `1h`. Numbers without unit are seconds.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use quartz_cli::bench::parse_duration;
+///
+/// assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+/// assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+/// assert_eq!(parse_duration("2m"), Ok(Duration::from_secs(120)));
+/// assert_eq!(parse_duration("1.5"), Ok(Duration::from_millis(1500)));
+/// assert!(parse_duration("soon").is_err());
+/// ```
+pub fn parse_duration(input: &str) -> Result<Duration, String> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(input.len());
+
+    let (value, unit) = input.split_at(split);
+    let value: f64 = value
+        .parse()
+        .map_err(|_| format!("invalid duration: {input}"))?;
+
+    let seconds = match unit {
+        "ms" => value / 1000.0,
+        "" | "s" => value,
+        "m" => value * 60.0,
+        "h" => value * 3600.0,
+        _ => {
+            return Err(format!(
+                "invalid duration unit: {unit}. Expected ms, s, m or h"
+            ))
+        }
+    };
+
+    Ok(Duration::from_secs_f64(seconds))
+}
+
+/// Nearest-rank percentile of `sorted` latencies, with `p` from 0 to 100.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use quartz_cli::bench::percentile;
+///
+/// let sorted: Vec<Duration> = (1..=10).map(Duration::from_millis).collect();
+///
+/// assert_eq!(percentile(&sorted, 50.0), Duration::from_millis(5));
+/// assert_eq!(percentile(&sorted, 90.0), Duration::from_millis(9));
+/// assert_eq!(percentile(&sorted, 99.0), Duration::from_millis(10));
+/// assert_eq!(percentile(&[], 50.0), Duration::ZERO);
+/// ```
+pub fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Result of a single benchmark request.
+pub struct Sample {
+    pub latency: Duration,
+
+    /// Response status code, or the error that prevented a response
+    pub outcome: Result<u16, String>,
+}
+
+/// Latency statistics, in milliseconds.
+#[derive(Debug, Default, Serialize)]
+pub struct Latency {
+    pub min: f64,
+    pub mean: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub requests: usize,
+
+    /// Wall time of the whole run, in seconds
+    pub duration: f64,
+
+    /// Requests per second
+    pub throughput: f64,
+
+    /// Latency of requests that got a response
+    pub latency: Latency,
+
+    /// Count of responses by status code
+    pub status: BTreeMap<u16, usize>,
+
+    /// Count of failed requests by error message
+    pub errors: BTreeMap<String, usize>,
+}
+
+impl Report {
+    pub fn new(samples: &[Sample], elapsed: Duration) -> Self {
+        let mut status = BTreeMap::new();
+        let mut errors = BTreeMap::new();
+        let mut latencies = Vec::new();
+
+        for sample in samples {
+            match &sample.outcome {
+                Ok(code) => {
+                    *status.entry(*code).or_insert(0) += 1;
+                    latencies.push(sample.latency);
+                }
+                Err(err) => *errors.entry(err.to_owned()).or_insert(0) += 1,
+            }
+        }
+
+        latencies.sort();
+
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        let latency = match (latencies.first(), latencies.last()) {
+            (Some(min), Some(max)) => Latency {
+                min: ms(*min),
+                mean: ms(latencies.iter().sum::<Duration>() / latencies.len() as u32),
+                p50: ms(percentile(&latencies, 50.0)),
+                p90: ms(percentile(&latencies, 90.0)),
+                p99: ms(percentile(&latencies, 99.0)),
+                max: ms(*max),
+            },
+            _ => Latency::default(),
+        };
+
+        let duration = elapsed.as_secs_f64();
+        let throughput = if duration > 0.0 {
+            samples.len() as f64 / duration
+        } else {
+            0.0
+        };
+
+        Self {
+            requests: samples.len(),
+            duration,
+            throughput,
+            latency,
+            status,
+            errors,
+        }
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Requests:    {}", self.requests)?;
+        writeln!(f, "Duration:    {:.2}s", self.duration)?;
+        writeln!(f, "Throughput:  {:.2} req/s", self.throughput)?;
+        writeln!(
+            f,
+            "Latency:     min {:.2}ms, mean {:.2}ms, p50 {:.2}ms, p90 {:.2}ms, p99 {:.2}ms, max {:.2}ms",
+            self.latency.min,
+            self.latency.mean,
+            self.latency.p50,
+            self.latency.p90,
+            self.latency.p99,
+            self.latency.max
+        )?;
+
+        let status = self
+            .status
+            .iter()
+            .map(|(code, count)| format!("{code}: {count}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        writeln!(f, "Status:      {status}")?;
+
+        for (err, count) in &self.errors {
+            writeln!(f, "Error:       {err} ({count})")?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,107 @@
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chrono::{SecondsFormat, Utc};
+use rand::{distributions::Alphanumeric, Rng};
+
+/// Generates the value of a built-in variable, given its name and arguments. Every call
+/// results in a new value.
+///
+/// # Examples
+///
+/// ```
+/// use quartz_cli::builtin::generate;
+///
+/// let n: i64 = generate("randomInt 5 7").unwrap().parse().unwrap();
+/// assert!((5..=7).contains(&n));
+///
+/// assert_eq!(generate("base64 hello world").unwrap(), "aGVsbG8gd29ybGQ=");
+/// assert!(generate("randomEmail").unwrap().ends_with("@example.com"));
+/// assert_eq!(generate("randomInt ten"), None);
+///
+/// let id = generate("uuid").unwrap();
+/// assert_eq!(id.len(), 36);
+/// assert_ne!(id, generate("uuid").unwrap());
+/// assert_eq!(generate("nothing"), None);
+/// ```
+pub fn generate(expr: &str) -> Option<String> {
+    let expr = expr.trim();
+
+    if let Some(var) = expr.strip_prefix("env.") {
+        return env(var);
+    }
+
+    let (name, args) = expr.split_once(' ').unwrap_or((expr, ""));
+
+    let value = match name {
+        "uuid" => uuid(),
+        "timestamp" => Utc::now().timestamp().to_string(),
+        "isoTimestamp" => Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        "randomInt" => {
+            let mut bounds = args.split_whitespace().map(|n| n.parse::<i64>());
+            let min = bounds.next().unwrap_or(Ok(0)).ok()?;
+            let max = bounds.next().unwrap_or(Ok(1000)).ok()?;
+
+            rand::thread_rng()
+                .gen_range(min.min(max)..=max.max(min))
+                .to_string()
+        }
+        "randomEmail" => format!("{}@example.com", random_string(12).to_lowercase()),
+        "base64" => STANDARD.encode(args),
+        _ => return None,
+    };
+
+    Some(value)
+}
+
+/// Value of a process environment variable, given as `NAME` or `NAME:-DEFAULT`. The default is
+/// used when the variable is unset or empty.
+///
+/// # Examples
+///
+/// ```
+/// use quartz_cli::builtin::generate;
+///
+/// std::env::set_var("QUARTZ_DOC_KEY", "secret");
+///
+/// assert_eq!(generate("env.QUARTZ_DOC_KEY").unwrap(), "secret");
+/// assert_eq!(generate("env.QUARTZ_DOC_KEY:-none").unwrap(), "secret");
+/// assert_eq!(generate("env.QUARTZ_DOC_UNSET:-none").unwrap(), "none");
+/// assert_eq!(generate("env.QUARTZ_DOC_UNSET:-").unwrap(), "");
+/// assert_eq!(generate("env.QUARTZ_DOC_UNSET"), None);
+/// ```
+fn env(var: &str) -> Option<String> {
+    let (name, default) = match var.split_once(":-") {
+        Some((name, default)) => (name, Some(default)),
+        None => (var, None),
+    };
+
+    match std::env::var(name.trim()) {
+        Ok(value) if !value.is_empty() || default.is_none() => Some(value),
+        _ => default.map(String::from),
+    }
+}
+
+/// Random version 4 UUID, as described by RFC 9562.
+fn uuid() -> String {
+    let mut bytes: [u8; 16] = rand::thread_rng().gen();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+fn random_string(len: usize) -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect()
+}
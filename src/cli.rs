@@ -24,7 +24,9 @@ pub enum Cmd {
     /// Initialize quartz
     Init(action::init::Args),
     /// Send request using the current handle's endpoint and outputs the response
-    Send(action::send::Args),
+    Send(Box<action::send::Args>),
+    /// Send an endpoint many times concurrently and report its performance
+    Bench(action::bench::Args),
     /// Create a new handle
     Create(action::handle::CreateArgs),
     /// Switch handle or edit its endpoint
@@ -87,6 +89,11 @@ pub enum Cmd {
         #[command(subcommand)]
         command: VarCmd,
     },
+    /// Manage current environment's encrypted secrets
+    Secret {
+        #[command(subcommand)]
+        command: SecretCmd,
+    },
     /// Manage configuration for quartz
     Config {
         #[command(subcommand)]
@@ -135,6 +142,45 @@ pub enum QueryCmd {
 
 #[derive(Debug, Subcommand)]
 pub enum HeaderCmd {
+    /// Print a header value
+    Get {
+        key: String,
+
+        /// Use headers shared by a handle with its descendants
+        #[arg(long, value_name = "HANDLE")]
+        scope: Option<String>,
+    },
+
+    /// Add new or existent header. Expects "key: value" format
+    Set {
+        header: Vec<String>,
+
+        /// Share headers with every endpoint under a handle
+        #[arg(long, value_name = "HANDLE")]
+        scope: Option<String>,
+    },
+
+    /// Remove a header
+    #[command(name = "rm", alias = "remove")]
+    Rm {
+        key: Vec<String>,
+
+        /// Remove headers shared by a handle with its descendants
+        #[arg(long, value_name = "HANDLE")]
+        scope: Option<String>,
+    },
+
+    /// Print headers
+    #[command(name = "ls", alias = "list")]
+    Ls {
+        /// List headers shared by a handle with its descendants
+        #[arg(long, value_name = "HANDLE")]
+        scope: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum EnvHeaderCmd {
     /// Print a header value
     Get { key: String },
 
@@ -173,7 +219,7 @@ pub enum ShowCmd {
     /// Display environment cookies
     Cookies(action::cookie::PrintArgs),
     /// Generate code snippet for endpoint
-    Snippet(action::snippet::Args),
+    Snippet(Box<action::snippet::Args>),
     /// Display endpoint configuration file
     Endpoint,
 }
@@ -231,9 +277,18 @@ pub enum EnvCmd {
     /// Delete a environment
     #[command(name = "rm", alias = "remove")]
     Rm(action::env::RmArgs),
+
+    /// Open an editor to modify environment settings
+    Edit(action::env::EditArgs),
+
+    /// Add variables from a dotenv file to an environment
+    Import(action::env::ImportArgs),
+
+    /// Print variables of an environment as dotenv, JSON or TOML
+    Export(action::env::ExportArgs),
     Header {
         #[command(subcommand)]
-        command: HeaderCmd,
+        command: EnvHeaderCmd,
     },
 }
 
@@ -253,5 +308,21 @@ pub enum VarCmd {
 
     /// Display the list of variables
     #[command(name = "ls", alias = "list")]
+    Ls(action::var::LsArgs),
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SecretCmd {
+    /// Display decrypted secret value
+    Get(action::secret::GetArgs),
+
+    /// Add a new or existent secret value
+    Set(action::secret::SetArgs),
+
+    /// Remove secrets
+    Rm(action::secret::RmArgs),
+
+    /// Display the list of secret names
+    #[command(name = "ls", alias = "list")]
     Ls,
 }
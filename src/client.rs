@@ -0,0 +1,1044 @@
+use std::fmt::Display;
+use std::future::Future;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::str::FromStr;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use base64::Engine;
+use hyper::client::connect::{Connected, Connection};
+use hyper::client::HttpConnector;
+use hyper::header::HeaderValue;
+use hyper::service::Service;
+use hyper::{Client, Request, StatusCode, Uri, Version};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf};
+use tokio::net::TcpStream;
+use tokio_native_tls::{TlsConnector, TlsStream};
+
+use crate::QuartzResult;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// HTTP protocol version used to send requests.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HttpVersion {
+    /// HTTP/1.1 only.
+    #[default]
+    #[serde(rename = "1.1")]
+    Http11,
+
+    /// HTTP/2 when the server agrees on it through ALPN, otherwise HTTP/1.1.
+    ///
+    /// Plain HTTP connections always use HTTP/1.1.
+    #[serde(rename = "2")]
+    Http2,
+
+    /// HTTP/2 without negotiation, both over TLS and plain HTTP.
+    #[serde(rename = "2-prior-knowledge")]
+    Http2PriorKnowledge,
+}
+
+impl HttpVersion {
+    /// Protocols offered to the server during TLS handshake.
+    fn alpn(&self) -> &'static [&'static str] {
+        match self {
+            Self::Http11 => &["http/1.1"],
+            Self::Http2 => &["h2", "http/1.1"],
+            Self::Http2PriorKnowledge => &["h2"],
+        }
+    }
+
+    /// Version requests should be built with. Negotiated versions are only known after
+    /// connecting, so they start as HTTP/1.1.
+    pub fn request_version(&self) -> Version {
+        match self {
+            Self::Http2PriorKnowledge => Version::HTTP_2,
+            _ => Version::HTTP_11,
+        }
+    }
+}
+
+/// TLS settings for HTTPS connections.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, clap::Args)]
+pub struct Tls {
+    /// CA certificate to verify servers against, in PEM format
+    #[arg(long, value_name = "FILE")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cacert: Option<PathBuf>,
+
+    /// Client certificate for mutual TLS, in PEM format. It may also contain the private key
+    #[arg(long, value_name = "FILE")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cert: Option<PathBuf>,
+
+    /// Private key of the client certificate, in PEM format
+    #[arg(long, value_name = "FILE", requires = "cert")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<PathBuf>,
+
+    /// Skip verification of server certificates
+    #[arg(long, short = 'k')]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub insecure: bool,
+}
+
+impl Tls {
+    /// Fills unset settings with the ones from `other`.
+    pub fn or(self, other: Tls) -> Self {
+        Self {
+            cacert: self.cacert.or(other.cacert),
+            cert: self.cert.clone().or(other.cert),
+            // A key only makes sense along with its certificate
+            key: if self.cert.is_some() {
+                self.key
+            } else {
+                other.key
+            },
+            insecure: self.insecure || other.insecure,
+        }
+    }
+
+    /// Resolves relative paths from `dir`.
+    pub fn relative_to(self, dir: &Path) -> Self {
+        let resolve = |path: Option<PathBuf>| path.map(|p| dir.join(p));
+
+        Self {
+            cacert: resolve(self.cacert),
+            cert: resolve(self.cert),
+            key: resolve(self.key),
+            insecure: self.insecure,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cacert.is_none() && self.cert.is_none() && self.key.is_none() && !self.insecure
+    }
+
+    fn connector(&self, alpn: &[&str]) -> QuartzResult<native_tls::TlsConnector> {
+        let mut builder = native_tls::TlsConnector::builder();
+        builder.request_alpns(alpn);
+
+        if let Some(path) = &self.cacert {
+            let pem = std::fs::read(path)
+                .unwrap_or_else(|err| panic!("failed to read {}: {err}", path.display()));
+
+            builder.add_root_certificate(native_tls::Certificate::from_pem(&pem)?);
+        }
+
+        if let Some(path) = &self.cert {
+            let cert = std::fs::read(path)
+                .unwrap_or_else(|err| panic!("failed to read {}: {err}", path.display()));
+
+            let key = match &self.key {
+                Some(path) => std::fs::read(path)
+                    .unwrap_or_else(|err| panic!("failed to read {}: {err}", path.display())),
+                None => cert.clone(),
+            };
+
+            builder.identity(native_tls::Identity::from_pkcs8(&cert, &key)?);
+        }
+
+        if self.insecure {
+            builder
+                .danger_accept_invalid_certs(true)
+                .danger_accept_invalid_hostnames(true);
+        }
+
+        Ok(builder.build()?)
+    }
+}
+
+/// Forces a host and port onto a specific address, bypassing name resolution.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Resolve {
+    pub host: String,
+    pub port: u16,
+    pub addr: IpAddr,
+}
+
+impl FromStr for Resolve {
+    type Err = String;
+
+    /// Parses `<HOST>:<PORT>:<ADDR>`, where IPv6 addresses may be enclosed in brackets.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quartz_cli::client::Resolve;
+    ///
+    /// let resolve: Resolve = "api.local:443:127.0.0.1".parse().unwrap();
+    /// assert_eq!(resolve.host, "api.local");
+    /// assert_eq!(resolve.port, 443);
+    /// assert_eq!(resolve.addr.to_string(), "127.0.0.1");
+    ///
+    /// let resolve: Resolve = "api.local:80:[::1]".parse().unwrap();
+    /// assert_eq!(resolve.addr.to_string(), "::1");
+    /// assert_eq!(resolve.to_string(), "api.local:80:[::1]");
+    ///
+    /// assert!("api.local:127.0.0.1".parse::<Resolve>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("invalid resolve entry: {s}. Expected <HOST>:<PORT>:<ADDR>");
+
+        let (host, rest) = s.split_once(':').ok_or_else(err)?;
+        let (port, addr) = rest.split_once(':').ok_or_else(err)?;
+        let addr = addr.trim_start_matches('[').trim_end_matches(']');
+
+        Ok(Self {
+            host: host.to_ascii_lowercase(),
+            port: port.parse().map_err(|_| err())?,
+            addr: addr.parse().map_err(|_| err())?,
+        })
+    }
+}
+
+impl Display for Resolve {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.addr {
+            IpAddr::V4(addr) => write!(f, "{}:{}:{addr}", self.host, self.port),
+            IpAddr::V6(addr) => write!(f, "{}:{}:[{addr}]", self.host, self.port),
+        }
+    }
+}
+
+impl TryFrom<String> for Resolve {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Resolve> for String {
+    fn from(value: Resolve) -> Self {
+        value.to_string()
+    }
+}
+
+/// Where connections are opened, in place of the request host.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, clap::Args)]
+pub struct Route {
+    /// Connect through this Unix domain socket instead of the network
+    #[arg(long, value_name = "PATH")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unix_socket: Option<PathBuf>,
+
+    /// Connect to ADDR when requesting HOST:PORT. This argument can be passed multiple times
+    #[arg(long, value_name = "HOST:PORT:ADDR")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub resolve: Vec<Resolve>,
+}
+
+impl Route {
+    /// Fills unset settings with the ones from `other`. Entries from both are kept to resolve
+    /// hosts, preferring the ones in `self`.
+    pub fn or(mut self, other: Route) -> Self {
+        self.resolve.extend(other.resolve);
+
+        Self {
+            unix_socket: self.unix_socket.or(other.unix_socket),
+            resolve: self.resolve,
+        }
+    }
+
+    /// Resolves relative paths from `dir`.
+    pub fn relative_to(self, dir: &Path) -> Self {
+        Self {
+            unix_socket: self.unix_socket.map(|p| dir.join(p)),
+            resolve: self.resolve,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.unix_socket.is_none() && self.resolve.is_empty()
+    }
+
+    /// Address forced onto the host and port of `uri`, if any.
+    fn lookup(&self, uri: &Uri) -> Option<SocketAddr> {
+        let host = uri.host()?.trim_start_matches('[').trim_end_matches(']');
+        let port = uri.port_u16().unwrap_or(match uri.scheme_str() {
+            Some("https") => 443,
+            _ => 80,
+        });
+
+        self.resolve
+            .iter()
+            .find(|r| r.port == port && r.host.eq_ignore_ascii_case(host))
+            .map(|r| SocketAddr::new(r.addr, port))
+    }
+}
+
+/// Error for operations that took longer than allowed.
+#[derive(Debug)]
+pub struct TimeoutError(pub &'static str);
+
+impl Display for TimeoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} timed out", self.0)
+    }
+}
+
+impl std::error::Error for TimeoutError {}
+
+/// Parses a number of seconds that fits in a [`Duration`].
+///
+/// # Examples
+///
+/// ```
+/// use quartz_cli::client::parse_seconds;
+///
+/// assert_eq!(parse_seconds("1.5"), Ok(1.5));
+/// assert!(parse_seconds("-1").is_err());
+/// assert!(parse_seconds("inf").is_err());
+/// assert!(parse_seconds("1e20").is_err());
+/// ```
+pub fn parse_seconds(input: &str) -> Result<f64, String> {
+    let seconds: f64 = input
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid number of seconds: {input}"))?;
+
+    check_seconds(seconds)
+}
+
+fn check_seconds(seconds: f64) -> Result<f64, String> {
+    match Duration::try_from_secs_f64(seconds) {
+        Ok(_) => Ok(seconds),
+        Err(_) => Err(format!(
+            "invalid number of seconds: {seconds}. Expected a finite, non-negative number"
+        )),
+    }
+}
+
+fn deserialize_seconds<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match Option::<f64>::deserialize(deserializer)? {
+        Some(seconds) => check_seconds(seconds)
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+        None => Ok(None),
+    }
+}
+
+/// Time limits for sending requests, in seconds.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, clap::Args)]
+pub struct Timeout {
+    /// Maximum time in seconds to establish a connection
+    #[arg(long = "connect-timeout", value_name = "SECONDS", value_parser = parse_seconds)]
+    #[serde(
+        default,
+        deserialize_with = "deserialize_seconds",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub connect: Option<f64>,
+
+    /// Maximum time in seconds to wait for data from the server
+    #[arg(long = "read-timeout", value_name = "SECONDS", value_parser = parse_seconds)]
+    #[serde(
+        default,
+        deserialize_with = "deserialize_seconds",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub read: Option<f64>,
+
+    /// Maximum time in seconds for the whole operation, including redirects and retries
+    #[arg(long = "max-time", short = 'm', value_name = "SECONDS", value_parser = parse_seconds)]
+    #[serde(
+        default,
+        deserialize_with = "deserialize_seconds",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub total: Option<f64>,
+}
+
+impl Timeout {
+    /// Fills unset limits with the ones from `other`.
+    pub fn or(self, other: Timeout) -> Self {
+        Self {
+            connect: self.connect.or(other.connect),
+            read: self.read.or(other.read),
+            total: self.total.or(other.total),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.connect.is_none() && self.read.is_none() && self.total.is_none()
+    }
+
+    fn duration(seconds: Option<f64>) -> Option<Duration> {
+        seconds.map(|s| Duration::try_from_secs_f64(s.max(0.0)).unwrap_or(Duration::MAX))
+    }
+
+    pub fn connect(&self) -> Option<Duration> {
+        Self::duration(self.connect)
+    }
+
+    pub fn read(&self) -> Option<Duration> {
+        Self::duration(self.read)
+    }
+
+    pub fn total(&self) -> Option<Duration> {
+        Self::duration(self.total)
+    }
+
+    /// Runs `future`, failing with [`TimeoutError`] if it outlasts `limit`.
+    pub async fn limit<F, T>(
+        limit: Option<Duration>,
+        what: &'static str,
+        future: F,
+    ) -> QuartzResult<T>
+    where
+        F: Future<Output = QuartzResult<T>>,
+    {
+        match limit {
+            Some(limit) => tokio::time::timeout(limit, future)
+                .await
+                .unwrap_or_else(|_| Err(Box::new(TimeoutError(what)))),
+            None => future.await,
+        }
+    }
+}
+
+/// Failure that makes a request be sent again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum RetryCondition {
+    /// Connection could not be established or was interrupted.
+    Connection,
+
+    /// Connection or server took too long to answer.
+    Timeout,
+
+    /// Any status within a class, like 5xx.
+    Class(u16),
+
+    /// An exact status code.
+    Status(u16),
+}
+
+impl RetryCondition {
+    /// Whether a response with `status` should be retried.
+    pub fn matches_status(&self, status: StatusCode) -> bool {
+        match self {
+            Self::Class(class) => status.as_u16() / 100 == *class,
+            Self::Status(code) => status.as_u16() == *code,
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for RetryCondition {
+    type Err = String;
+
+    /// # Examples
+    ///
+    /// ```
+    /// use quartz_cli::client::RetryCondition;
+    ///
+    /// assert_eq!("connection".parse(), Ok(RetryCondition::Connection));
+    /// assert_eq!("timeout".parse(), Ok(RetryCondition::Timeout));
+    /// assert_eq!("5xx".parse(), Ok(RetryCondition::Class(5)));
+    /// assert_eq!("429".parse(), Ok(RetryCondition::Status(429)));
+    ///
+    /// assert!("6xx".parse::<RetryCondition>().is_err());
+    /// assert!("sometimes".parse::<RetryCondition>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_ascii_lowercase();
+
+        match s.as_str() {
+            "connection" => return Ok(Self::Connection),
+            "timeout" => return Ok(Self::Timeout),
+            _ => (),
+        }
+
+        if let Some(class) = s.strip_suffix("xx") {
+            if let Ok(class @ 1..=5) = class.parse::<u16>() {
+                return Ok(Self::Class(class));
+            }
+        } else if let Ok(code @ 100..=599) = s.parse::<u16>() {
+            return Ok(Self::Status(code));
+        }
+
+        Err(format!(
+            "invalid retry condition: {s}. Expected connection, timeout, a status class (5xx) or code (429)"
+        ))
+    }
+}
+
+impl TryFrom<String> for RetryCondition {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<RetryCondition> for String {
+    fn from(value: RetryCondition) -> Self {
+        value.to_string()
+    }
+}
+
+impl Display for RetryCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Connection => write!(f, "connection"),
+            Self::Timeout => write!(f, "timeout"),
+            Self::Class(class) => write!(f, "{class}xx"),
+            Self::Status(code) => write!(f, "{code}"),
+        }
+    }
+}
+
+/// Policy to send requests again after failures.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, clap::Args)]
+pub struct Retry {
+    /// Retry failed requests up to N times
+    #[arg(long = "retry", value_name = "N")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count: Option<u32>,
+
+    /// Seconds to wait before the first retry. It doubles for each following one
+    #[arg(long = "retry-backoff", value_name = "SECONDS", value_parser = parse_seconds)]
+    #[serde(
+        default,
+        deserialize_with = "deserialize_seconds",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub backoff: Option<f64>,
+
+    /// Which failure is retried: connection, timeout, a status class (5xx) or code (429). This
+    /// argument can be passed multiple times
+    #[arg(long = "retry-on", value_name = "CONDITION")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on: Vec<RetryCondition>,
+}
+
+impl Retry {
+    const DEFAULT_BACKOFF: f64 = 1.0;
+    const DEFAULT_CONDITIONS: [RetryCondition; 4] = [
+        RetryCondition::Connection,
+        RetryCondition::Timeout,
+        RetryCondition::Status(429),
+        RetryCondition::Class(5),
+    ];
+
+    /// Fills unset settings with the ones from `other`.
+    pub fn or(self, other: Retry) -> Self {
+        Self {
+            count: self.count.or(other.count),
+            backoff: self.backoff.or(other.backoff),
+            on: if self.on.is_empty() {
+                other.on
+            } else {
+                self.on
+            },
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count.is_none() && self.backoff.is_none() && self.on.is_empty()
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count.unwrap_or_default()
+    }
+
+    /// Time to wait before the retry number `attempt`, starting from zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use quartz_cli::client::Retry;
+    ///
+    /// let retry = Retry {
+    ///     backoff: Some(0.5),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// assert_eq!(retry.delay(0), Duration::from_millis(500));
+    /// assert_eq!(retry.delay(1), Duration::from_secs(1));
+    /// assert_eq!(retry.delay(2), Duration::from_secs(2));
+    /// ```
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let backoff = self.backoff.unwrap_or(Self::DEFAULT_BACKOFF).max(0.0);
+
+        Duration::try_from_secs_f64(backoff * 2f64.powi(attempt.min(16) as i32))
+            .unwrap_or(Duration::MAX)
+    }
+
+    /// Conditions that trigger a retry. Defaults to connection errors, timeouts, 429 and 5xx
+    /// statuses.
+    pub fn conditions(&self) -> &[RetryCondition] {
+        if self.on.is_empty() {
+            &Self::DEFAULT_CONDITIONS
+        } else {
+            &self.on
+        }
+    }
+
+    /// Whether a failed request with `err` should be retried.
+    pub fn matches_error(&self, err: &(dyn std::error::Error + 'static)) -> bool {
+        let mut source = Some(err);
+        let mut is_timeout = false;
+
+        while let Some(err) = source {
+            is_timeout |= err.is::<TimeoutError>();
+            source = err.source();
+        }
+
+        let condition = if is_timeout {
+            RetryCondition::Timeout
+        } else if err.is::<hyper::Error>() {
+            RetryCondition::Connection
+        } else {
+            return false;
+        };
+
+        self.conditions().contains(&condition)
+    }
+
+    /// Whether a response with `status` should be retried.
+    pub fn matches_status(&self, status: StatusCode) -> bool {
+        self.conditions().iter().any(|c| c.matches_status(status))
+    }
+}
+
+/// HTTP proxy that requests are routed through.
+#[derive(Debug, Clone)]
+pub struct Proxy {
+    uri: Uri,
+    authorization: Option<HeaderValue>,
+}
+
+impl Proxy {
+    /// Parses a proxy URL, with optional credentials. The scheme defaults to "http".
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quartz_cli::client::Proxy;
+    ///
+    /// let proxy = Proxy::parse("user:p%40ss@localhost:3128").unwrap();
+    ///
+    /// assert_eq!(proxy.uri().to_string(), "http://localhost:3128/");
+    /// assert_eq!(proxy.authorization().unwrap(), "Basic dXNlcjpwQHNz");
+    ///
+    /// assert!(Proxy::parse("localhost:3128").unwrap().authorization().is_none());
+    /// ```
+    pub fn parse(input: &str) -> QuartzResult<Self> {
+        let input = input.trim();
+        let (scheme, rest) = input.split_once("://").unwrap_or(("http", input));
+        let rest = rest.trim_end_matches('/');
+
+        let (credentials, authority) = match rest.rsplit_once('@') {
+            Some((credentials, authority)) => (Some(credentials), authority),
+            None => (None, rest),
+        };
+
+        let uri = Uri::builder()
+            .scheme(scheme)
+            .authority(authority)
+            .path_and_query("/")
+            .build()?;
+
+        let authorization = match credentials {
+            Some(credentials) => {
+                let (user, password) = credentials.split_once(':').unwrap_or((credentials, ""));
+                let user = percent_encoding::percent_decode_str(user).decode_utf8()?;
+                let password = percent_encoding::percent_decode_str(password).decode_utf8()?;
+
+                let token =
+                    base64::engine::general_purpose::STANDARD.encode(format!("{user}:{password}"));
+
+                Some(HeaderValue::from_str(&format!("Basic {token}"))?)
+            }
+            None => None,
+        };
+
+        Ok(Self { uri, authorization })
+    }
+
+    pub fn uri(&self) -> &Uri {
+        &self.uri
+    }
+
+    /// Value for the Proxy-Authorization header, when credentials are given.
+    pub fn authorization(&self) -> Option<&HeaderValue> {
+        self.authorization.as_ref()
+    }
+}
+
+/// Which proxy, if any, each request goes through.
+#[derive(Debug, Clone, Default)]
+pub struct Proxies {
+    http: Option<Proxy>,
+    https: Option<Proxy>,
+    no_proxy: Vec<String>,
+}
+
+impl Proxies {
+    /// Uses `proxy` for every request. Without it, proxies are taken from HTTP_PROXY, HTTPS_PROXY
+    /// and ALL_PROXY environment variables.
+    ///
+    /// Hosts listed in NO_PROXY are always reached directly.
+    pub fn new(proxy: Option<&str>) -> QuartzResult<Self> {
+        let no_proxy = Self::var("no_proxy")
+            .map(|v| {
+                v.split(',')
+                    .map(|host| host.trim().to_ascii_lowercase())
+                    .filter(|host| !host.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        if let Some(proxy) = proxy {
+            let proxy = Proxy::parse(proxy)?;
+
+            return Ok(Self {
+                http: Some(proxy.clone()),
+                https: Some(proxy),
+                no_proxy,
+            });
+        }
+
+        let all = Self::var("all_proxy");
+        let parse = |value: Option<String>| value.or(all.clone()).map(|v| Proxy::parse(&v));
+
+        Ok(Self {
+            http: parse(Self::var("http_proxy")).transpose()?,
+            https: parse(Self::var("https_proxy")).transpose()?,
+            no_proxy,
+        })
+    }
+
+    /// Reads an environment variable in lowercase, then uppercase.
+    fn var(name: &str) -> Option<String> {
+        std::env::var(name)
+            .or_else(|_| std::env::var(name.to_ascii_uppercase()))
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+    }
+
+    /// Finds the proxy to reach `uri`.
+    pub fn get(&self, uri: &Uri) -> Option<&Proxy> {
+        let host = uri.host()?.trim_matches(['[', ']']).to_ascii_lowercase();
+
+        let bypass = self.no_proxy.iter().any(|entry| {
+            let entry = match entry.rsplit_once(':') {
+                Some((name, port)) if port.parse::<u16>().is_ok() && !name.contains(':') => name,
+                _ => entry,
+            };
+            let entry = entry.trim_start_matches('.');
+
+            entry == "*" || host == entry || host.ends_with(&format!(".{entry}"))
+        });
+
+        if bypass {
+            return None;
+        }
+
+        match uri.scheme_str() {
+            Some("https") => self.https.as_ref(),
+            _ => self.http.as_ref(),
+        }
+    }
+
+    /// Adds Proxy-Authorization header to `req` when it goes through a proxy with credentials.
+    /// Requests tunneled through CONNECT, either HTTPS or HTTP/2, are authenticated by the
+    /// connector instead.
+    pub fn authorize<T>(&self, req: &mut Request<T>) {
+        if req.uri().scheme_str() == Some("https") || req.version() == Version::HTTP_2 {
+            return;
+        }
+
+        if let Some(authorization) = self.get(req.uri()).and_then(Proxy::authorization) {
+            req.headers_mut()
+                .insert(hyper::header::PROXY_AUTHORIZATION, authorization.clone());
+        }
+    }
+}
+
+/// Connector that opens plain TCP or TLS streams, reporting to hyper which protocol was
+/// negotiated.
+#[derive(Clone)]
+pub struct Connector {
+    http: HttpConnector,
+    tls: TlsConnector,
+    proxies: Proxies,
+    route: Route,
+    timeout: Option<Duration>,
+
+    /// Whether plain HTTP requests must be tunneled too, since proxies only forward HTTP/1
+    tunnel_http: bool,
+}
+
+impl Connector {
+    /// Asks `proxy` to open a tunnel to `uri` with the CONNECT method.
+    async fn tunnel(mut tcp: TcpStream, proxy: &Proxy, uri: &Uri) -> QuartzResult<TcpStream> {
+        let host = uri.host().unwrap_or_default();
+        let port = uri.port_u16().unwrap_or(match uri.scheme_str() {
+            Some("https") => 443,
+            _ => 80,
+        });
+
+        let mut req = format!("CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\n");
+        if let Some(authorization) = proxy.authorization() {
+            req.push_str(&format!(
+                "Proxy-Authorization: {}\r\n",
+                authorization.to_str()?
+            ));
+        }
+        req.push_str("\r\n");
+
+        tcp.write_all(req.as_bytes()).await?;
+
+        let mut res = Vec::new();
+        let mut buf = [0; 1024];
+        while !res.windows(4).any(|w| w == b"\r\n\r\n") {
+            let n = tcp.read(&mut buf).await?;
+
+            if n == 0 || res.len() > 64 * 1024 {
+                return Err("proxy closed connection before tunneling".into());
+            }
+
+            res.extend_from_slice(&buf[..n]);
+        }
+
+        let res = String::from_utf8_lossy(&res);
+        let status_line = res.lines().next().unwrap_or_default();
+
+        match status_line.split_whitespace().nth(1) {
+            Some(code) if code.starts_with('2') => Ok(tcp),
+            _ => Err(format!("proxy refused to tunnel: {status_line}").into()),
+        }
+    }
+}
+
+impl Connector {
+    #[cfg(unix)]
+    async fn unix(path: &Path, is_https: bool) -> Result<Stream, BoxError> {
+        if is_https {
+            return Err("HTTPS is not supported over Unix sockets".into());
+        }
+
+        let stream = tokio::net::UnixStream::connect(path)
+            .await
+            .map_err(|err| format!("{}: {err}", path.display()))?;
+
+        Ok(Stream::Unix(stream))
+    }
+
+    #[cfg(not(unix))]
+    async fn unix(_path: &Path, _is_https: bool) -> Result<Stream, BoxError> {
+        Err("Unix sockets are not supported on this platform".into())
+    }
+}
+
+impl Service<Uri> for Connector {
+    type Response = Stream;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.http.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let mut http = self.http.clone();
+        let tls = self.tls.clone();
+        let proxy = self.proxies.get(&uri).cloned();
+        let route = self.route.clone();
+        let timeout = self.timeout;
+        let tunnel_http = self.tunnel_http;
+
+        let connect = async move {
+            let is_https = uri.scheme_str() == Some("https");
+            let host = uri.host().unwrap_or_default().to_string();
+
+            if let Some(path) = &route.unix_socket {
+                return Connector::unix(path, is_https).await;
+            }
+
+            let tcp = match &proxy {
+                Some(proxy) => {
+                    let tcp = http.call(proxy.uri().clone()).await?;
+
+                    if !is_https && !tunnel_http {
+                        return Ok(Stream::Proxy(tcp));
+                    }
+
+                    Connector::tunnel(tcp, proxy, &uri)
+                        .await
+                        .map_err(|err| err.to_string())?
+                }
+                None => match route.lookup(&uri) {
+                    Some(addr) => {
+                        let scheme = uri.scheme_str().unwrap_or("http");
+                        http.call(format!("{scheme}://{addr}").parse()?).await?
+                    }
+                    None => http.call(uri).await?,
+                },
+            };
+
+            if !is_https {
+                return Ok(Stream::Tcp(tcp));
+            }
+
+            let tls = tls.connect(&host, tcp).await?;
+            Ok::<_, BoxError>(Stream::Tls(Box::new(tls)))
+        };
+
+        Box::pin(async move {
+            match timeout {
+                Some(timeout) => tokio::time::timeout(timeout, connect)
+                    .await
+                    .unwrap_or_else(|_| Err(Box::new(TimeoutError("connection")))),
+                None => connect.await,
+            }
+        })
+    }
+}
+
+pub enum Stream {
+    Tcp(TcpStream),
+    /// Connection to a proxy that forwards plain HTTP requests.
+    Proxy(TcpStream),
+    Tls(Box<TlsStream<TcpStream>>),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixStream),
+}
+
+impl Connection for Stream {
+    fn connected(&self) -> Connected {
+        match self {
+            Self::Tcp(s) => s.connected(),
+            Self::Proxy(s) => s.connected().proxy(true),
+            Self::Tls(s) => {
+                let connected = s.get_ref().get_ref().get_ref().connected();
+
+                match s.get_ref().negotiated_alpn() {
+                    Ok(Some(protocol)) if protocol == b"h2" => connected.negotiated_h2(),
+                    _ => connected,
+                }
+            }
+            #[cfg(unix)]
+            Self::Unix(_) => Connected::new(),
+        }
+    }
+}
+
+impl AsyncRead for Stream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(s) | Self::Proxy(s) => Pin::new(s).poll_read(cx, buf),
+            Self::Tls(s) => Pin::new(s).poll_read(cx, buf),
+            #[cfg(unix)]
+            Self::Unix(s) => Pin::new(s).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for Stream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Self::Tcp(s) | Self::Proxy(s) => Pin::new(s).poll_write(cx, buf),
+            Self::Tls(s) => Pin::new(s).poll_write(cx, buf),
+            #[cfg(unix)]
+            Self::Unix(s) => Pin::new(s).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(s) | Self::Proxy(s) => Pin::new(s).poll_flush(cx),
+            Self::Tls(s) => Pin::new(s).poll_flush(cx),
+            #[cfg(unix)]
+            Self::Unix(s) => Pin::new(s).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(s) | Self::Proxy(s) => Pin::new(s).poll_shutdown(cx),
+            Self::Tls(s) => Pin::new(s).poll_shutdown(cx),
+            #[cfg(unix)]
+            Self::Unix(s) => Pin::new(s).poll_shutdown(cx),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ClientBuilder {
+    version: HttpVersion,
+    proxies: Proxies,
+    tls: Tls,
+    route: Route,
+    connect_timeout: Option<Duration>,
+}
+
+impl ClientBuilder {
+    pub fn version(&mut self, value: HttpVersion) -> &mut Self {
+        self.version = value;
+        self
+    }
+
+    pub fn proxies(&mut self, value: Proxies) -> &mut Self {
+        self.proxies = value;
+        self
+    }
+
+    pub fn tls(&mut self, value: Tls) -> &mut Self {
+        self.tls = value;
+        self
+    }
+
+    pub fn route(&mut self, value: Route) -> &mut Self {
+        self.route = value;
+        self
+    }
+
+    pub fn connect_timeout(&mut self, value: Option<Duration>) -> &mut Self {
+        self.connect_timeout = value;
+        self
+    }
+
+    pub fn build(&self) -> QuartzResult<Client<Connector>> {
+        let mut http = HttpConnector::new();
+        http.enforce_http(false);
+
+        let tls = self.tls.connector(self.version.alpn())?;
+
+        let connector = Connector {
+            http,
+            tls: tls.into(),
+            proxies: self.proxies.clone(),
+            route: self.route.clone(),
+            timeout: self.connect_timeout,
+            tunnel_http: self.version == HttpVersion::Http2PriorKnowledge,
+        };
+
+        let client = Client::builder()
+            .http2_only(self.version == HttpVersion::Http2PriorKnowledge)
+            .build(connector);
+
+        Ok(client)
+    }
+}
@@ -0,0 +1,97 @@
+use std::io::{Read, Write};
+
+use flate2::{
+    read::{GzDecoder, ZlibDecoder},
+    write::{GzEncoder, ZlibEncoder},
+    Compression,
+};
+
+/// Content codings understood by quartz.
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Encoding {
+    Gzip,
+    Deflate,
+
+    /// Only decoding is supported
+    #[value(skip)]
+    Br,
+}
+
+impl Encoding {
+    /// Value for Accept-Encoding header listing every supported coding.
+    pub const ACCEPT: &'static str = "gzip, deflate, br";
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Encoding::Gzip => "gzip",
+            Encoding::Deflate => "deflate",
+            Encoding::Br => "br",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "gzip" | "x-gzip" => Some(Encoding::Gzip),
+            "deflate" => Some(Encoding::Deflate),
+            "br" => Some(Encoding::Br),
+            _ => None,
+        }
+    }
+
+    /// # Panics
+    ///
+    /// Program is terminated when encoding with brotli.
+    pub fn encode(&self, input: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self {
+            Encoding::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(input)?;
+                encoder.finish()
+            }
+            Encoding::Deflate => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(input)?;
+                encoder.finish()
+            }
+            Encoding::Br => panic!("brotli compression is not supported"),
+        }
+    }
+
+    pub fn decode(&self, input: &[u8]) -> std::io::Result<Vec<u8>> {
+        let mut output = Vec::new();
+
+        match self {
+            Encoding::Gzip => GzDecoder::new(input).read_to_end(&mut output)?,
+            Encoding::Deflate => ZlibDecoder::new(input).read_to_end(&mut output)?,
+            Encoding::Br => {
+                brotli_decompressor::Decompressor::new(input, 4096).read_to_end(&mut output)?
+            }
+        };
+
+        Ok(output)
+    }
+}
+
+/// Decodes `body` according to a Content-Encoding header value, undoing codings in the reverse
+/// order they were applied. Unknown codings are left as is.
+///
+/// # Examples
+///
+/// ```
+/// use quartz_cli::compression::{decode, Encoding};
+///
+/// let body = Encoding::Gzip.encode(b"hello").unwrap();
+/// let body = Encoding::Deflate.encode(&body).unwrap();
+///
+/// assert_eq!(decode("gzip, deflate", body).unwrap(), b"hello");
+/// assert_eq!(decode("identity", b"hello".to_vec()).unwrap(), b"hello");
+/// ```
+pub fn decode(content_encoding: &str, mut body: Vec<u8>) -> std::io::Result<Vec<u8>> {
+    for name in content_encoding.rsplit(',') {
+        if let Some(encoding) = Encoding::from_name(name) {
+            body = encoding.decode(&body)?;
+        }
+    }
+
+    Ok(body)
+}
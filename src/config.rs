@@ -1,10 +1,21 @@
 use serde::{Deserialize, Serialize};
 use std::{fs::OpenOptions, io::Write, path::PathBuf};
 
+use crate::client::{Retry, Timeout};
+use crate::redact::DEFAULT_HEADERS;
+
 #[derive(Default, Serialize, Deserialize)]
 pub struct Config {
     pub preferences: Preferences,
     pub ui: UiConfig,
+
+    /// Default time limits for sending requests, unless set by endpoints.
+    #[serde(default, skip_serializing_if = "Timeout::is_empty")]
+    pub timeout: Timeout,
+
+    /// Default retry policy, unless set by endpoints.
+    #[serde(default, skip_serializing_if = "Retry::is_empty")]
+    pub retry: Retry,
 }
 
 impl Config {
@@ -51,6 +62,10 @@ impl Config {
 pub struct Preferences {
     editor: Option<String>,
     pager: Option<String>,
+    proxy: Option<String>,
+
+    /// Headers hidden from history and snippets
+    redact: Option<Vec<String>>,
 }
 
 impl Preferences {
@@ -87,6 +102,38 @@ impl Preferences {
     {
         self.pager = Some(pager.into());
     }
+
+    /// Proxy used for every request. When unset, proxy environment variables are used instead.
+    pub fn proxy(&self) -> Option<&str> {
+        self.proxy.as_deref()
+    }
+
+    pub fn set_proxy<T>(&mut self, proxy: T)
+    where
+        T: Into<String>,
+    {
+        self.proxy = Some(proxy.into());
+    }
+
+    /// Headers hidden from history and snippets, defaulting to [`DEFAULT_HEADERS`].
+    pub fn redact(&self) -> Vec<String> {
+        match &self.redact {
+            Some(headers) => headers.clone(),
+            None => DEFAULT_HEADERS.map(String::from).to_vec(),
+        }
+    }
+
+    /// Sets redacted headers from a comma-separated list. An empty list redacts nothing.
+    pub fn set_redact(&mut self, headers: &str) {
+        self.redact = Some(
+            headers
+                .split(',')
+                .map(str::trim)
+                .filter(|h| !h.is_empty())
+                .map(String::from)
+                .collect(),
+        );
+    }
 }
 
 #[derive(Serialize, Deserialize, Default)]
@@ -102,10 +149,8 @@ impl UiConfig {
             clicolor == "0"
         } else if let Ok(clicolor) = std::env::var("CLICOLOR") {
             clicolor == "0"
-        } else if let Some(colors) = self.colors {
-            colors
         } else {
-            true
+            self.colors.unwrap_or(true)
         }
     }
 
@@ -4,6 +4,7 @@ use hyper::http::uri::Scheme;
 use std::{
     collections::HashSet,
     convert::Infallible,
+    fmt::Display,
     hash::Hash,
     ops::{Deref, DerefMut},
     path::{Path, PathBuf},
@@ -259,7 +260,7 @@ impl Hash for Cookie {
     }
 }
 
-impl ToString for Cookie {
+impl Display for Cookie {
     /// Converts a given [`Cookie`] into a Netspace HTTP Cookie file line.
     ///
     ///# Examples
@@ -280,12 +281,13 @@ impl ToString for Cookie {
     /// assert_eq!(cookie.to_string(),
     /// "httpbin.org\tTRUE\t/somepath\tFALSE\t0\tmysecret\tsupersecretkey");
     /// ```
-    fn to_string(&self) -> String {
-        format!(
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
             "{}\t{}\t{}\t{}\t{}\t{}\t{}",
             *self.domain,
             self.subdomains.to_string().to_uppercase(),
-            self.path.to_string(),
+            self.path,
             self.secure.to_string().to_uppercase(),
             self.expires_at,
             self.name,
@@ -421,16 +423,13 @@ impl DerefMut for CookieJar {
     }
 }
 
-impl ToString for CookieJar {
-    fn to_string(&self) -> String {
-        let mut jar = String::new();
-
+impl Display for CookieJar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for cookie in self.iter() {
-            jar.push_str(&cookie.to_string());
-            jar.push('\n');
+            writeln!(f, "{cookie}")?;
         }
 
-        jar
+        Ok(())
     }
 }
 
@@ -606,7 +605,7 @@ impl From<&str> for PathAttr {
     }
 }
 
-impl ToString for PathAttr {
+impl Display for PathAttr {
     /// Converts this into a Path attribute-value string.
     ///
     /// # Examples
@@ -615,12 +614,8 @@ impl ToString for PathAttr {
     /// use quartz_cli::cookie::PathAttr;
     ///
     /// ```
-    fn to_string(&self) -> String {
-        let mut s = String::from("/");
-
-        s.push_str(&self.join("/"));
-
-        s
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "/{}", self.join("/"))
     }
 }
 
@@ -0,0 +1,183 @@
+/// Parses dotenv content into `(key, value)` pairs, in the order they appear.
+///
+/// Blank lines, `#` comments and `export` prefixes are ignored. Values may be enclosed in
+/// single quotes, taken as is, or double quotes, which accept `\n`, `\t`, `\"` and `\\` escapes.
+/// Quoted values may span multiple lines. Unquoted values are trimmed and end at a ` #`
+/// comment.
+///
+/// # Examples
+///
+/// ```
+/// use quartz_cli::dotenv::parse;
+///
+/// let content = r#"
+/// ## Database
+/// export HOST=localhost # local only
+/// PORT = 5432
+/// KEY='-----BEGIN KEY-----
+/// abc
+/// -----END KEY-----'
+/// GREETING="hello\n\"world\""
+/// EMPTY=
+/// "#;
+///
+/// assert_eq!(
+///     parse(content).unwrap(),
+///     vec![
+///         ("HOST".to_string(), "localhost".to_string()),
+///         ("PORT".to_string(), "5432".to_string()),
+///         ("KEY".to_string(), "-----BEGIN KEY-----\nabc\n-----END KEY-----".to_string()),
+///         ("GREETING".to_string(), "hello\n\"world\"".to_string()),
+///         ("EMPTY".to_string(), String::new()),
+///     ]
+/// );
+///
+/// assert!(parse("NAME=\"unterminated").is_err());
+/// assert!(parse("no value").is_err());
+/// ```
+pub fn parse(input: &str) -> Result<Vec<(String, String)>, String> {
+    let mut pairs = Vec::new();
+    let mut lines = input.lines().enumerate();
+
+    while let Some((n, line)) = lines.next() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, rest) = line
+            .split_once('=')
+            .ok_or_else(|| format!("line {}: expected KEY=VALUE", n + 1))?;
+
+        let key = key.trim();
+        if key.is_empty() {
+            return Err(format!("line {}: missing key", n + 1));
+        }
+
+        let rest = rest.trim_start();
+        let value = match rest.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let mut raw = rest[1..].to_string();
+
+                let end = loop {
+                    if let Some(end) = closing_quote(&raw, quote) {
+                        break end;
+                    }
+
+                    let (_, next) = lines
+                        .next()
+                        .ok_or_else(|| format!("line {}: unterminated quote", n + 1))?;
+                    raw.push('\n');
+                    raw.push_str(next);
+                };
+
+                let tail = raw[end + 1..].trim();
+                if !tail.is_empty() && !tail.starts_with('#') {
+                    return Err(format!("line {}: unexpected text after quote", n + 1));
+                }
+
+                raw.truncate(end);
+
+                if quote == '"' {
+                    unescape(&raw)
+                } else {
+                    raw
+                }
+            }
+            _ => match rest.find(" #") {
+                Some(comment) => rest[..comment].trim().to_string(),
+                None => rest.trim().to_string(),
+            },
+        };
+
+        pairs.push((key.to_string(), value));
+    }
+
+    Ok(pairs)
+}
+
+/// Writes `value` so [`parse`] reads it back the same, quoting it only when needed.
+///
+/// # Examples
+///
+/// ```
+/// use quartz_cli::dotenv::quote;
+///
+/// assert_eq!(quote("localhost"), "localhost");
+/// assert_eq!(quote(r#"{"id":1}"#), r#"{"id":1}"#);
+/// assert_eq!(quote("two\nlines"), r#""two\nlines""#);
+/// assert_eq!(quote("'quoted'"), r#""'quoted'""#);
+/// assert_eq!(quote(" padded #1"), r#"" padded #1""#);
+/// ```
+pub fn quote(value: &str) -> String {
+    let needs_quotes = value.contains(['\n', '\r'])
+        || value.starts_with(['"', '\''])
+        || value.contains(" #")
+        || value.trim() != value;
+
+    if !needs_quotes {
+        return value.to_string();
+    }
+
+    format!("\"{}\"", escape(value))
+}
+
+/// Escapes `value` to be enclosed in double quotes, undone by [`unescape`].
+pub(crate) fn escape(value: &str) -> String {
+    let mut output = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c => output.push(c),
+        }
+    }
+
+    output
+}
+
+/// Position of the quote closing a value, skipping escaped ones in double quotes.
+fn closing_quote(input: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+
+    for (i, c) in input.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quote == '"' => escaped = true,
+            c if c == quote => return Some(i),
+            _ => (),
+        }
+    }
+
+    None
+}
+
+pub(crate) fn unescape(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut chars = input.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            output.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => output.push('\n'),
+            Some('r') => output.push('\r'),
+            Some('t') => output.push('\t'),
+            Some(c @ ('"' | '\\')) => output.push(c),
+            Some(c) => {
+                output.push('\\');
+                output.push(c);
+            }
+            None => output.push('\\'),
+        }
+    }
+
+    output
+}
@@ -1,4 +1,5 @@
 use colored::Colorize;
+use hyper::header::{HeaderName, HeaderValue};
 use hyper::http::uri::InvalidUri;
 use hyper::{Body, Request, Uri};
 use serde::{Deserialize, Serialize};
@@ -7,11 +8,17 @@ use std::fmt::Display;
 use std::io::Write;
 use std::ops::{Deref, DerefMut};
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
+use crate::auth::Auth;
+use crate::client::{HttpVersion, Retry, Timeout};
 use crate::env::{Env, Variables};
+use crate::form::{Form, FormEncoding};
+use crate::grpc::Grpc;
 use crate::state::StateField;
+use crate::template;
 use crate::tree::Tree;
-use crate::{Ctx, PairMap};
+use crate::{Ctx, PairMap, QuartzResult};
 
 #[derive(Default, Debug, Serialize, Deserialize, Clone)]
 pub struct Query(pub HashMap<String, String>);
@@ -59,6 +66,19 @@ impl Headers {
         }
         headers
     }
+
+    /// Adds these headers to `req`, unless it sets them already.
+    pub fn apply<T>(&self, req: &mut Request<T>) -> QuartzResult {
+        for (key, value) in self.iter() {
+            let key = HeaderName::from_str(key)?;
+
+            if !req.headers().contains_key(&key) {
+                req.headers_mut().insert(key, HeaderValue::from_str(value)?);
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl Deref for Headers {
@@ -100,6 +120,70 @@ impl PairMap<'_> for Headers {
     }
 }
 
+/// Variables and headers a handle shares with its descendants, which may override them.
+///
+/// They are kept in `variables` and `headers` files of the handle directory, so empty handles
+/// can have them as well.
+#[derive(Default, Debug, Clone)]
+pub struct Scope {
+    pub variables: Variables,
+    pub headers: Headers,
+}
+
+impl Scope {
+    /// Reads the scope of a single handle directory.
+    pub fn parse(dir: &Path) -> Self {
+        let mut scope = Self::default();
+
+        if let Ok(content) = std::fs::read_to_string(dir.join("variables")) {
+            scope.variables = Variables::parse(&content);
+        }
+        if let Ok(content) = std::fs::read_to_string(dir.join("headers")) {
+            scope.headers = Headers::parse(&content);
+        }
+
+        scope
+    }
+
+    /// Layers scopes from the top-level handle down to `dir`, closer ones taking precedence.
+    pub fn resolve(dir: &Path) -> Self {
+        let dirs: Vec<&Path> = dir
+            .ancestors()
+            .take_while(|dir| dir.join("spec").exists())
+            .collect();
+
+        let mut scope = Self::default();
+        for dir in dirs.into_iter().rev() {
+            let Self { variables, headers } = Self::parse(dir);
+
+            scope.variables.extend(variables.0);
+            for (key, value) in headers.0 {
+                scope.headers.retain(|k, _| !k.eq_ignore_ascii_case(&key));
+                scope.headers.insert(key, value);
+            }
+        }
+
+        scope
+    }
+
+    pub fn write(&self, dir: &Path) -> QuartzResult {
+        for (filename, content) in [
+            ("variables", self.variables.to_string()),
+            ("headers", self.headers.to_string()),
+        ] {
+            let path = dir.join(filename);
+
+            if !content.is_empty() {
+                std::fs::write(path, content)?;
+            } else if path.exists() {
+                std::fs::remove_file(path)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct EndpointHandle {
     /// List of ordered parent names
@@ -119,18 +203,43 @@ pub struct Endpoint {
     /// List of (key, value) pairs.
     pub headers: Headers,
 
-    /// Variable values applied from a [`Env`]
+    /// HTTP version used to send requests.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<HttpVersion>,
+
+    /// Time limits for sending requests.
+    #[serde(default, skip_serializing_if = "Timeout::is_empty")]
+    pub timeout: Timeout,
+
+    /// Policy to send requests again after failures.
+    #[serde(default, skip_serializing_if = "Retry::is_empty")]
+    pub retry: Retry,
+
+    /// Credentials to authorize requests. Inherited from parents and environment when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<Auth>,
+
+    /// Makes this endpoint a gRPC call instead of a plain HTTP request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grpc: Option<Grpc>,
+
+    /// Variable values applied from a [`Env`]. Built-in variables are only resolved once it is
+    /// set
     #[serde(skip_serializing, skip_deserializing)]
-    pub variables: Variables,
+    pub variables: Option<Variables>,
 
     #[serde(skip_serializing, skip_deserializing)]
     pub path: PathBuf,
 
     #[serde(skip_serializing, skip_deserializing)]
     pub body: Option<String>,
+
+    /// Multipart form fields, sent instead of the body when present.
+    #[serde(skip_serializing, skip_deserializing)]
+    pub form: Option<Form>,
 }
 
-#[derive(Debug, clap::Args)]
+#[derive(Debug, Clone, clap::Args)]
 #[group(multiple = false)]
 pub struct ContentTypeGroup {
     /// Use JSON data in request body with the appropriate content-type header
@@ -140,9 +249,31 @@ pub struct ContentTypeGroup {
     /// Use raw data in request body
     #[arg(long = "data", short = 'd', value_name = "DATA")]
     pub raw: Option<String>,
+
+    /// Add or patch a multipart form field. Prefix a path with "@" to upload a file. This
+    /// argument can be passed multiple times
+    #[arg(long, short = 'F', value_name = "NAME=VALUE|NAME=@FILE")]
+    pub form: Vec<String>,
+
+    /// Add or patch a field of an URL-encoded form. This argument can be passed multiple times
+    #[arg(long, value_name = "NAME=VALUE")]
+    pub form_urlencoded: Vec<String>,
+}
+
+impl ContentTypeGroup {
+    /// Form fields to be patched, with their encoding.
+    pub fn form(&self) -> Option<(FormEncoding, &[String])> {
+        if !self.form.is_empty() {
+            Some((FormEncoding::Multipart, &self.form))
+        } else if !self.form_urlencoded.is_empty() {
+            Some((FormEncoding::UrlEncoded, &self.form_urlencoded))
+        } else {
+            None
+        }
+    }
 }
 
-#[derive(Default, Debug, clap::Args)]
+#[derive(Default, Debug, Clone, clap::Args)]
 pub struct EndpointPatch {
     /// Patch request URL
     #[arg(long)]
@@ -162,6 +293,14 @@ pub struct EndpointPatch {
 
     #[command(flatten)]
     pub data: Option<ContentTypeGroup>,
+
+    /// Patch .proto file used by a gRPC endpoint
+    #[arg(long, value_name = "FILE")]
+    pub proto: Option<PathBuf>,
+
+    /// Patch gRPC method to be called. Makes the endpoint a gRPC call
+    #[arg(long, value_name = "SERVICE/METHOD")]
+    pub rpc: Option<String>,
 }
 
 impl EndpointPatch {
@@ -170,6 +309,9 @@ impl EndpointPatch {
             || self.method.is_some()
             || !self.query.is_empty()
             || !self.headers.is_empty()
+            || self.data.as_ref().is_some_and(|data| data.form().is_some())
+            || self.proto.is_some()
+            || self.rpc.is_some()
     }
 }
 
@@ -263,6 +405,9 @@ impl EndpointHandle {
         if self.endpoint(ctx).is_some() {
             let _ = std::fs::remove_file(self.dir(ctx).join("endpoint.toml"));
             let _ = std::fs::remove_file(self.dir(ctx).join("body"));
+            for encoding in [FormEncoding::Multipart, FormEncoding::UrlEncoded] {
+                let _ = std::fs::remove_file(self.dir(ctx).join(encoding.filename()));
+            }
         }
     }
 
@@ -372,6 +517,35 @@ impl Endpoint {
             self.query.set(input);
         }
 
+        if let Some(rpc) = &src.rpc {
+            let (service, method) = Grpc::parse_rpc(rpc)
+                .unwrap_or_else(|| panic!("malformed rpc. Expected <SERVICE>/<METHOD>"));
+
+            if let Some(grpc) = &mut self.grpc {
+                grpc.service = service;
+                grpc.method = method;
+            } else {
+                let proto = src
+                    .proto
+                    .clone()
+                    .unwrap_or_else(|| panic!("missing .proto file. Use --proto <FILE>"));
+
+                self.grpc = Some(Grpc {
+                    proto,
+                    service,
+                    method,
+                    include: Vec::new(),
+                });
+            }
+        }
+
+        if let Some(proto) = &src.proto {
+            match &mut self.grpc {
+                Some(grpc) => grpc.proto = proto.to_owned(),
+                None => panic!("missing gRPC method. Use --rpc <SERVICE>/<METHOD>"),
+            }
+        }
+
         if let Some(data) = &src.data {
             if let Some(maybe_json) = &data.json {
                 self.headers
@@ -379,9 +553,23 @@ impl Endpoint {
 
                 if let Some(json) = maybe_json {
                     self.body = Some(json.to_owned());
+                    self.form = Some(Form::default());
                 }
             } else if let Some(raw) = &data.raw {
                 self.body = Some(raw.to_owned());
+                self.form = Some(Form::default());
+            } else if let Some((encoding, fields)) = data.form() {
+                self.form();
+
+                // Changing encoding starts a new form
+                let form = match &mut self.form {
+                    Some(form) if form.encoding == encoding => form,
+                    form => form.insert(Form::new(encoding)),
+                };
+
+                for input in fields {
+                    form.set(input);
+                }
             }
         }
     }
@@ -393,10 +581,8 @@ impl Endpoint {
     pub fn load_body(&mut self) -> Option<&String> {
         match std::fs::read_to_string(self.path.join("body")) {
             Ok(mut content) => {
-                for (key, value) in self.variables.iter() {
-                    let key_match = format!("{{{{{}}}}}", key);
-
-                    content = content.replace(&key_match, value);
+                if let Some(variables) = &self.variables {
+                    content = variables.apply(&content);
                 }
 
                 if content.trim().is_empty() {
@@ -418,6 +604,44 @@ impl Endpoint {
         }
     }
 
+    pub fn load_form(&mut self) -> Option<&Form> {
+        if self.path.as_os_str().is_empty() {
+            return None;
+        }
+
+        for encoding in [FormEncoding::Multipart, FormEncoding::UrlEncoded] {
+            if let Ok(content) = std::fs::read_to_string(self.path.join(encoding.filename())) {
+                let mut form = Form::parse(&content, encoding);
+                if let Some(variables) = &self.variables {
+                    form.apply_variables(variables);
+                }
+
+                self.form = Some(form);
+                break;
+            }
+        }
+
+        self.form.as_ref()
+    }
+
+    /// Returns multipart form fields, if this endpoint has any.
+    pub fn form(&mut self) -> Option<&Form> {
+        if self.form.is_none() {
+            self.load_form();
+        }
+
+        self.form.as_ref().filter(|form| !form.is_empty())
+    }
+
+    /// Directory that holds the `.quartz` folder of this endpoint.
+    pub fn project_dir(&self) -> &Path {
+        self.path
+            .ancestors()
+            .find(|dir| dir.file_name().is_some_and(|name| name == ".quartz"))
+            .and_then(|dir| dir.parent())
+            .unwrap_or(Path::new(""))
+    }
+
     pub fn set_handle(&mut self, ctx: &Ctx, handle: &EndpointHandle) {
         self.path = handle.dir(ctx).to_path_buf();
     }
@@ -453,39 +677,108 @@ impl Endpoint {
         }
     }
 
+    /// Resolves authentication for this endpoint: its own, the closest parent's or `env`'s,
+    /// with variables applied.
+    ///
+    /// An explicit Authorization header takes precedence, so tokens are not fetched for nothing.
+    pub fn resolve_auth(&self, env: &Env) -> Option<Auth> {
+        if self
+            .headers
+            .keys()
+            .any(|key| key.eq_ignore_ascii_case("authorization"))
+        {
+            return None;
+        }
+
+        let auth = self.auth.clone().or_else(|| {
+            // Handles without an endpoint are skipped, so they can group endpoints under a parent
+            self.path
+                .ancestors()
+                .skip(1)
+                .take_while(|dir| dir.join("spec").exists())
+                .filter_map(|dir| Self::from_dir(dir).ok())
+                .find_map(|endpoint| endpoint.auth)
+        });
+
+        let mut auth = auth.or_else(|| env.config.auth.clone())?;
+        if auth == Auth::None {
+            return None;
+        }
+
+        auth.apply_variables(&env.variables);
+
+        Some(auth)
+    }
+
+    /// Adds headers scoped to this endpoint's handle and parents, unless it sets them already.
+    pub fn resolve_headers(&mut self) {
+        for (key, value) in Scope::resolve(&self.path).headers.0 {
+            if !self.headers.keys().any(|k| k.eq_ignore_ascii_case(&key)) {
+                self.headers.insert(key, value);
+            }
+        }
+    }
+
     pub fn apply_env(&mut self, env: &Env) {
         self.resolve_url();
+        self.resolve_headers();
+        self.apply_variables(&env.variables);
+    }
 
-        for (key, value) in env.variables.iter() {
-            let key_match = format!("{{{{{}}}}}", key); // {{key}}
+    /// Renders `variables` in the request, without reading parents or scopes again. URL and
+    /// headers are expected to be resolved already, as [`Endpoint::apply_env`] does.
+    pub fn apply_variables(&mut self, variables: &Variables) {
+        self.url = variables.apply(&self.url);
+        self.method = variables.apply(&self.method);
 
-            self.url = self.url.replace(&key_match, value);
-            self.method = self.method.replace(&key_match, value);
+        *self.headers = self
+            .headers
+            .iter()
+            .map(|(key, value)| (variables.apply(key), variables.apply(value)))
+            .collect();
+
+        *self.query = self
+            .query
+            .iter()
+            .map(|(key, value)| (variables.apply(key), variables.apply(value)))
+            .collect();
+
+        // Contents loaded before the environment was known
+        if let Some(body) = &mut self.body {
+            *body = variables.apply(body);
+        }
 
-            *self.headers = self
-                .headers
-                .iter()
-                .map(|(h_key, h_value)| {
-                    let h_key = &h_key.replace(&key_match, value);
-                    let h_value = &h_value.replace(&key_match, value);
+        if let Some(form) = &mut self.form {
+            form.apply_variables(variables);
+        }
 
-                    (h_key.clone(), h_value.clone())
-                })
-                .collect();
+        self.variables = Some(variables.clone());
+    }
 
-            *self.query = self
-                .query
-                .iter()
-                .map(|(h_key, h_value)| {
-                    let h_key = &h_key.replace(&key_match, value);
-                    let h_value = &h_value.replace(&key_match, value);
+    /// Variable notations left in the request after [`Endpoint::apply_env`], sorted and without
+    /// duplicates.
+    pub fn unresolved_variables(&mut self) -> Vec<String> {
+        let mut sources = vec![self.url.clone(), self.method.clone()];
 
-                    (h_key.clone(), h_value.clone())
-                })
-                .collect();
+        for (key, value) in self.headers.iter().chain(self.query.iter()) {
+            sources.push(key.clone());
+            sources.push(value.clone());
         }
 
-        self.variables = env.variables.clone();
+        if let Some(form) = self.form() {
+            sources.extend(form.fields.iter().map(|field| field.to_string()));
+        } else if let Some(body) = self.body() {
+            sources.push(body.clone());
+        }
+
+        let mut list: Vec<String> = sources
+            .iter()
+            .flat_map(|source| template::placeholders(source))
+            .collect();
+
+        list.sort();
+        list.dedup();
+        list
     }
 
     pub fn full_url(&self) -> Result<Uri, InvalidUri> {
@@ -512,21 +805,49 @@ impl Endpoint {
     }
 
     /// Returns the a [`Request`] consuming struct.
-    pub fn into_request(mut self) -> Result<Request<Body>, hyper::http::Error> {
+    ///
+    /// # Panics
+    ///
+    /// Program is terminated if a file of its form can not be read.
+    pub fn into_request(self) -> Result<Request<Body>, hyper::http::Error> {
+        Ok(self.into_raw_request()?.map(Body::from))
+    }
+
+    /// Returns the a [`Request`] with its whole body in memory, consuming struct.
+    ///
+    /// # Panics
+    ///
+    /// Program is terminated if a file of its form can not be read.
+    pub fn into_raw_request(mut self) -> Result<Request<Vec<u8>>, hyper::http::Error> {
         let mut builder = hyper::Request::builder().uri(&self.full_url()?);
 
         if let Ok(method) = hyper::Method::from_bytes(self.method.as_bytes()) {
             builder = builder.method(method);
         }
 
+        if let Some(form) = self.form().cloned() {
+            let (content_type, body) = form
+                .encode(self.project_dir())
+                .unwrap_or_else(|err| panic!("failed to read form file {err}"));
+
+            self.headers
+                .retain(|key, _| !key.eq_ignore_ascii_case("content-type"));
+            self.headers.insert("Content-Type".into(), content_type);
+
+            for (key, value) in self.headers.iter() {
+                builder = builder.header(key, value);
+            }
+
+            return builder.body(body);
+        }
+
         for (key, value) in self.headers.iter() {
             builder = builder.header(key, value);
         }
 
-        if let Some(body) = self.body() {
-            builder.body(body.to_owned().into())
-        } else {
-            builder.body(Body::empty())
+        match self.body() {
+            Some(body) => builder.body(body.to_owned().into_bytes()),
+            None => builder.body(Vec::new()),
         }
     }
 
@@ -572,6 +893,17 @@ impl Endpoint {
 
         file.write_all(toml_content.as_bytes())
             .unwrap_or_else(|_| panic!("failed to write to config file"));
+
+        if let Some(form) = &self.form {
+            for encoding in [FormEncoding::Multipart, FormEncoding::UrlEncoded] {
+                let _ = std::fs::remove_file(self.path.join(encoding.filename()));
+            }
+
+            if !form.is_empty() {
+                std::fs::write(self.path.join(form.encoding.filename()), form.to_string())
+                    .unwrap_or_else(|_| panic!("failed to write form file"));
+            }
+        }
     }
 }
 
@@ -581,10 +913,16 @@ impl Default for Endpoint {
             method: String::from("GET"),
             url: Default::default(),
             headers: Default::default(),
+            version: Default::default(),
+            timeout: Default::default(),
+            retry: Default::default(),
+            auth: Default::default(),
+            grpc: Default::default(),
             variables: Default::default(),
             query: Default::default(),
             path: Default::default(),
             body: Default::default(),
+            form: Default::default(),
         }
     }
 }
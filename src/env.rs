@@ -3,12 +3,20 @@ use std::{
     fmt::Display,
     io::Write,
     ops::{Deref, DerefMut},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
 use serde::{Deserialize, Serialize};
 
-use crate::{cookie::CookieJar, endpoint::Headers, Ctx, PairMap};
+use crate::{
+    auth::Auth,
+    client::{Route, Tls},
+    cookie::CookieJar,
+    dotenv,
+    endpoint::{Endpoint, Headers, Scope},
+    secret::Secrets,
+    Ctx, PairMap, QuartzResult,
+};
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct Variables(pub HashMap<String, String>);
@@ -30,7 +38,7 @@ impl DerefMut for Variables {
 impl Display for Variables {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for (key, value) in self.iter() {
-            writeln!(f, "{key}={value}")?;
+            writeln!(f, "{key}={}", Self::quote(value))?;
         }
 
         Ok(())
@@ -46,15 +54,129 @@ impl PairMap<'_> for Variables {
 }
 
 impl Variables {
+    /// Renders `{{key}}` notations in `input` with variable values and built-in ones such as
+    /// `{{$uuid}}`. See [`template::render`](crate::template::render).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quartz_cli::env::Variables;
+    ///
+    /// let variables = Variables::parse("user=admin\n");
+    ///
+    /// assert_eq!(variables.apply("{{user}}:{{pass}}"), "admin:{{pass}}");
+    /// assert_eq!(variables.apply("{{$base64 {{user}}}}"), "YWRtaW4=");
+    /// ```
+    pub fn apply(&self, input: &str) -> String {
+        crate::template::render(input, self)
+    }
+
+    /// Reads variables kept in a variables file, one "key=value" per line. Blank lines and
+    /// lines starting with `#` are skipped. Unlike dotenv files, values are taken as they are,
+    /// apart from quotes around them. Values written by [`Display`] in double quotes, such as
+    /// the ones with line breaks, are unescaped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quartz_cli::env::Variables;
+    ///
+    /// let variables = Variables::parse(
+    ///     "# team\nuser=admin\n\nnote=a #b\nquoted=\"x\"\nkey=\"a\\nb\"\n",
+    /// );
+    ///
+    /// assert_eq!(variables.get("user").unwrap(), "admin");
+    /// assert_eq!(variables.get("note").unwrap(), "a #b");
+    /// assert_eq!(variables.get("quoted").unwrap(), "x");
+    /// assert_eq!(variables.get("key").unwrap(), "a\nb");
+    /// assert_eq!(variables.len(), 4);
+    ///
+    /// assert_eq!(Variables::parse(&variables.to_string()).0, variables.0);
+    /// ```
     pub fn parse(file_content: &str) -> Self {
         let mut variables = Variables::default();
 
-        for var in file_content.split('\n').filter(|line| !line.is_empty()) {
-            variables.set(var);
+        for line in file_content.lines() {
+            if line.trim().is_empty() || line.trim_start().starts_with('#') {
+                continue;
+            }
+
+            match line.split_once('=') {
+                Some((key, value))
+                    if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') =>
+                {
+                    let value = dotenv::unescape(&value[1..value.len() - 1]);
+                    variables.insert(key.to_string(), value);
+                }
+                _ => variables.set(line),
+            }
         }
 
         variables
     }
+
+    /// Writes `value` so [`Variables::parse`] reads it back the same: in double quotes with
+    /// escapes if it has line breaks or would lose quotes around it, as is otherwise.
+    fn quote(value: &str) -> String {
+        if value.contains(['\n', '\r'])
+            || value.starts_with(['"', '\''])
+            || value.ends_with(['"', '\''])
+        {
+            format!("\"{}\"", dotenv::escape(value))
+        } else {
+            value.to_string()
+        }
+    }
+
+    /// Reads variables from a dotenv file.
+    pub fn from_file(path: &Path) -> QuartzResult<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let pairs = dotenv::parse(&content)?;
+
+        Ok(Variables(pairs.into_iter().collect()))
+    }
+
+    /// Variables given to a single command: the ones in `env_files`, later files taking
+    /// precedence, then `inputs` in "key=value" format over them.
+    pub fn overrides(env_files: &[PathBuf], inputs: &[String]) -> Self {
+        let mut overrides = Variables::default();
+        for path in env_files {
+            let variables = Variables::from_file(path)
+                .unwrap_or_else(|err| panic!("failed to read {}: {err}", path.display()));
+            overrides.extend(variables.0);
+        }
+        for input in inputs {
+            overrides.set(input);
+        }
+
+        overrides
+    }
+}
+
+/// Environment settings that are not variables nor headers.
+///
+/// It is kept in the environment's `env.toml` file, edited by the user.
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct EnvConfig {
+    /// Environment whose variables and headers are layered under this one's
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
+
+    /// TLS settings for requests sent in this environment
+    #[serde(default, skip_serializing_if = "Tls::is_empty")]
+    pub tls: Tls,
+
+    /// Unix socket and host resolution for requests sent in this environment
+    #[serde(flatten)]
+    pub route: Route,
+
+    /// Authentication for endpoints that do not set their own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<Auth>,
+}
+
+impl EnvConfig {
+    pub const FILENAME: &'static str = "env.toml";
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -62,6 +184,28 @@ pub struct Env {
     pub name: String,
     pub variables: Variables,
     pub headers: Headers,
+    pub config: EnvConfig,
+    pub secrets: Secrets,
+
+    /// Variables of the current user only, kept in `.quartz/user` so they are not shared
+    #[serde(skip)]
+    pub local: Variables,
+
+    /// Shared values of variables overridden by local ones
+    #[serde(skip)]
+    shadowed: Variables,
+
+    /// Values taken from the extended environment, for keys this one does not set
+    #[serde(skip)]
+    inherited: Variables,
+
+    /// Headers taken from the extended environment, for keys this one does not set
+    #[serde(skip)]
+    inherited_headers: Headers,
+
+    /// Environment this one extends, with its own chain resolved
+    #[serde(skip)]
+    base: Option<Box<Env>>,
 }
 
 impl Default for Env {
@@ -70,6 +214,13 @@ impl Default for Env {
             name: String::from("default"),
             variables: Variables::default(),
             headers: Headers::default(),
+            config: EnvConfig::default(),
+            secrets: Secrets::default(),
+            local: Variables::default(),
+            shadowed: Variables::default(),
+            inherited: Variables::default(),
+            inherited_headers: Headers::default(),
+            base: None,
         }
     }
 }
@@ -108,34 +259,227 @@ impl Env {
             .truncate(true)
             .open(self.dir(ctx).join("headers"))?;
 
-        if !self.variables.is_empty() {
-            var_file.write_all(format!("{}", self.variables).as_bytes())?;
+        let mut variables = self.variables.clone();
+        let mut headers = self.headers.clone();
+
+        // Local values are kept out, leaving the shared ones they override in place
+        for (key, value) in self.local.iter() {
+            if variables.get(key) == Some(value) {
+                match self.shadowed.get(key) {
+                    Some(shared) => variables.insert(key.to_string(), shared.to_string()),
+                    None => variables.remove(key),
+                };
+            }
         }
-        if !self.headers.0.is_empty() {
-            headers_file.write_all(format!("{}", self.headers).as_bytes())?;
+
+        // Inherited values are left to the base environment, so they keep following it
+        variables.retain(|key, value| self.inherited.get(key) != Some(value));
+        headers
+            .0
+            .retain(|key, value| self.inherited_headers.get(key) != Some(value));
+
+        if !variables.is_empty() {
+            var_file.write_all(format!("{}", variables).as_bytes())?;
+        }
+        if !headers.0.is_empty() {
+            headers_file.write_all(format!("{}", headers).as_bytes())?;
+        }
+
+        let secrets_path = self.dir(ctx).join(Secrets::FILENAME);
+        if !self.secrets.is_empty() {
+            std::fs::write(secrets_path, toml::to_string(&self.secrets)?)?;
+        } else if secrets_path.exists() {
+            std::fs::remove_file(secrets_path)?;
         }
 
         Ok(())
     }
 
+    /// Keeps variable `key` in this environment's own file, even when it has the value of the
+    /// extended environment.
+    pub fn own_variable(&mut self, key: &str) {
+        self.inherited.remove(key);
+    }
+
+    /// Keeps header `key` in this environment's own file, even when it has the value of the
+    /// extended environment.
+    pub fn own_header(&mut self, key: &str) {
+        self.inherited_headers.remove(key);
+    }
+
+    /// Directory of the current user's files for this environment.
+    pub fn local_dir(&self, ctx: &Ctx) -> PathBuf {
+        ctx.path().join("user").join("env").join(&self.name)
+    }
+
+    /// Writes local variables, which are not shared with other users of the project.
+    pub fn update_local(&self, ctx: &Ctx) -> QuartzResult {
+        let path = self.local_dir(ctx).join("variables");
+
+        if self.local.is_empty() {
+            if path.exists() {
+                std::fs::remove_file(path)?;
+            }
+
+            return Ok(());
+        }
+
+        std::fs::create_dir_all(self.local_dir(ctx))?;
+        std::fs::write(path, format!("{}", self.local))?;
+
+        Ok(())
+    }
+
     /// Returns `true` if this environment already exists on the quartz project.
     pub fn exists(&self, ctx: &Ctx) -> bool {
         self.dir(ctx).exists()
     }
 
+    /// Reads an environment, with variables and headers of the environments it extends.
+    ///
+    /// Fails if the chain of extended environments is broken or goes back to itself.
     pub fn parse(ctx: &Ctx, name: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::parse_chain(ctx, name, &mut Vec::new())
+    }
+
+    fn parse_chain(
+        ctx: &Ctx,
+        name: &str,
+        chain: &mut Vec<String>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
         let mut env = Self::new(name);
 
+        if chain.iter().any(|n| n == name) {
+            chain.push(name.to_string());
+            return Err(format!("environment cycle: {}", chain.join(" -> ")).into());
+        }
+        chain.push(name.to_string());
+
         if let Ok(var_contents) = std::fs::read_to_string(env.dir(ctx).join("variables")) {
             env.variables = Variables::parse(&var_contents);
         }
         if let Ok(header_contents) = std::fs::read_to_string(env.dir(ctx).join("headers")) {
             env.headers = Headers::parse(&header_contents);
         }
+        if let Ok(config_contents) = std::fs::read_to_string(env.dir(ctx).join(EnvConfig::FILENAME))
+        {
+            env.config = toml::from_str(&config_contents)?;
+        }
+        if let Ok(secrets_contents) = std::fs::read_to_string(env.dir(ctx).join(Secrets::FILENAME))
+        {
+            env.secrets = toml::from_str(&secrets_contents)?;
+        }
+        if let Ok(local_contents) = std::fs::read_to_string(env.local_dir(ctx).join("variables")) {
+            env.local = Variables::parse(&local_contents);
+
+            for (key, value) in env.local.iter() {
+                if let Some(shared) = env.variables.insert(key.to_string(), value.to_string()) {
+                    env.shadowed.insert(key.to_string(), shared);
+                }
+            }
+        }
+
+        if let Some(extends) = env.config.extends.clone() {
+            if !Self::new(&extends).exists(ctx) {
+                return Err(
+                    format!("environment {extends} extended by {name} does not exist").into(),
+                );
+            }
+
+            let base = Self::parse_chain(ctx, &extends, chain)?;
+
+            for (key, value) in base.variables.iter() {
+                if !env.variables.contains_key(key) {
+                    env.variables.insert(key.to_string(), value.to_string());
+                    env.inherited.insert(key.to_string(), value.to_string());
+                }
+            }
+            for (key, value) in base.headers.iter() {
+                if !env.headers.contains_key(key) {
+                    env.headers.insert(key.to_string(), value.to_string());
+                    env.inherited_headers
+                        .insert(key.to_string(), value.to_string());
+                }
+            }
+
+            env.base = Some(Box::new(base));
+        }
 
         Ok(env)
     }
 
+    /// Decrypts secrets into variables, so they are resolved like any other. Returns the secret
+    /// values, which must not be shown.
+    ///
+    /// Secrets of extended environments are inherited like their variables.
+    pub fn unlock_secrets(&mut self) -> QuartzResult<Vec<String>> {
+        let mut values = Vec::new();
+
+        if !self.secrets.is_empty() {
+            for (name, value) in self.secrets.decrypt()? {
+                values.push(value.clone());
+                self.variables.insert(name, value);
+            }
+        }
+
+        if let Some(base) = &mut self.base {
+            values.extend(base.unlock_secrets()?);
+
+            // Base variables are already merged, leaving only its secrets to be added
+            for (key, value) in base.variables.iter() {
+                self.variables
+                    .entry(key.to_string())
+                    .or_insert_with(|| value.to_string());
+            }
+        }
+
+        Ok(values)
+    }
+
+    /// Names of secrets of this environment and the ones it extends.
+    pub fn secret_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.secrets.names().cloned().collect();
+
+        if let Some(base) = &self.base {
+            names.extend(base.secret_names());
+        }
+
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Environment as seen by `endpoint`: variables scoped to its handle and parents take
+    /// precedence over this environment's shared ones, local variables and secrets over
+    /// them, and `overrides` over everything.
+    pub fn scoped(&self, endpoint: &Endpoint, overrides: &Variables) -> Env {
+        let mut env = self.clone();
+        env.variables
+            .extend(Scope::resolve(&endpoint.path).variables.0);
+
+        // Personal values were already merged, but must not lose to the handle's
+        env.variables.extend(self.local.0.clone());
+        for name in self.secret_names() {
+            if let Some(value) = self.variables.get(&name) {
+                env.variables.insert(name, value.to_string());
+            }
+        }
+
+        env.variables.extend(overrides.0.clone());
+
+        env
+    }
+
+    /// Variables with secret values replaced by a mask, safe to be shown.
+    pub fn masked_variables(&self) -> Variables {
+        let mut variables = self.variables.clone();
+        for name in self.secret_names() {
+            variables.insert(name, crate::secret::MASK.to_string());
+        }
+
+        variables
+    }
+
     pub fn cookie_jar(&self, ctx: &Ctx) -> CookieJar {
         let path = self.dir(ctx).join(CookieJar::FILENAME);
         let mut jar = CookieJar::read(&path).unwrap_or_default();
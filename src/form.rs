@@ -0,0 +1,260 @@
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+use crate::env::Variables;
+
+/// Characters escaped in `application/x-www-form-urlencoded` bodies, except for spaces.
+const URLENCODED: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'*')
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b' ');
+
+/// How form fields are encoded into a request body.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub enum FormEncoding {
+    /// `multipart/form-data`, which supports file uploads
+    #[default]
+    Multipart,
+
+    /// `application/x-www-form-urlencoded`
+    UrlEncoded,
+}
+
+impl FormEncoding {
+    /// Name of the file holding a form with this encoding in the handle directory.
+    pub fn filename(&self) -> &'static str {
+        match self {
+            Self::Multipart => "form",
+            Self::UrlEncoded => "form-urlencoded",
+        }
+    }
+}
+
+/// Value of a multipart form field.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormValue {
+    Text(String),
+
+    /// Path to a file uploaded as the field value
+    File(PathBuf),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormField {
+    pub name: String,
+    pub value: FormValue,
+}
+
+impl FormField {
+    /// Parses `<name>=<value>` or `<name>=@<path>` into a field.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quartz_cli::form::{FormField, FormValue};
+    ///
+    /// let field = FormField::parse("avatar=@images/me.png").unwrap();
+    /// assert_eq!(field.name, "avatar");
+    /// assert_eq!(field.value, FormValue::File("images/me.png".into()));
+    ///
+    /// let field = FormField::parse("name=Jane").unwrap();
+    /// assert_eq!(field.value, FormValue::Text("Jane".into()));
+    ///
+    /// assert_eq!(FormField::parse("name"), None);
+    /// ```
+    pub fn parse(input: &str) -> Option<Self> {
+        let (name, value) = input.split_once('=')?;
+
+        let value = match value.strip_prefix('@') {
+            Some(path) => FormValue::File(PathBuf::from(path)),
+            None => FormValue::Text(value.to_string()),
+        };
+
+        Some(Self {
+            name: name.to_string(),
+            value,
+        })
+    }
+}
+
+impl Display for FormField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.value {
+            FormValue::Text(text) => write!(f, "{}={}", self.name, text),
+            FormValue::File(path) => write!(f, "{}=@{}", self.name, path.display()),
+        }
+    }
+}
+
+/// Ordered fields sent as a form body.
+#[derive(Default, Debug, Clone)]
+pub struct Form {
+    pub encoding: FormEncoding,
+    pub fields: Vec<FormField>,
+}
+
+impl Form {
+    pub fn new(encoding: FormEncoding) -> Self {
+        Self {
+            encoding,
+            fields: Vec::new(),
+        }
+    }
+
+    pub fn parse(file_content: &str, encoding: FormEncoding) -> Self {
+        let mut form = Form::new(encoding);
+        for field in file_content.lines().filter(|line| !line.is_empty()) {
+            form.set(field);
+        }
+
+        form
+    }
+
+    /// Inserts a field, replacing any other with the same name. Only multipart forms upload
+    /// files, so "@" is kept as is by other encodings.
+    pub fn set(&mut self, input: &str) {
+        let mut field = FormField::parse(input)
+            .unwrap_or_else(|| panic!("malformed form field. Expected <name>=<value>"));
+
+        if let (FormEncoding::UrlEncoded, FormValue::File(path)) = (self.encoding, &field.value) {
+            field.value = FormValue::Text(format!("@{}", path.display()));
+        }
+
+        match self.fields.iter_mut().find(|f| f.name == field.name) {
+            Some(existing) => *existing = field,
+            None => self.fields.push(field),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Replaces `{{key}}` and built-in notations in field values.
+    pub fn apply_variables(&mut self, variables: &Variables) {
+        for field in self.fields.iter_mut() {
+            match &mut field.value {
+                FormValue::Text(text) => *text = variables.apply(text),
+                FormValue::File(path) => *path = variables.apply(&path.to_string_lossy()).into(),
+            }
+        }
+    }
+
+    /// Builds the request body, returning its content-type and bytes.
+    ///
+    /// Relative file paths are resolved from `root`.
+    pub fn encode(&self, root: &Path) -> std::io::Result<(String, Vec<u8>)> {
+        match self.encoding {
+            FormEncoding::Multipart => self.multipart(root),
+            FormEncoding::UrlEncoded => Ok((
+                "application/x-www-form-urlencoded".to_string(),
+                self.urlencoded().into_bytes(),
+            )),
+        }
+    }
+
+    /// Percent-encodes every field into a `application/x-www-form-urlencoded` string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quartz_cli::form::{Form, FormEncoding};
+    ///
+    /// let mut form = Form::new(FormEncoding::UrlEncoded);
+    /// form.set("grant_type=client_credentials");
+    /// form.set("scope=read write");
+    /// form.set("redirect=https://example.com/?a=1&b=2");
+    ///
+    /// assert_eq!(
+    ///     form.urlencoded(),
+    ///     "grant_type=client_credentials&scope=read+write&redirect=https%3A%2F%2Fexample.com%2F%3Fa%3D1%26b%3D2"
+    /// );
+    /// ```
+    pub fn urlencoded(&self) -> String {
+        let encode = |input: &str| {
+            utf8_percent_encode(input, URLENCODED)
+                .to_string()
+                .replace(' ', "+")
+        };
+
+        self.fields
+            .iter()
+            .map(|field| {
+                let value = match &field.value {
+                    FormValue::Text(text) => text.to_owned(),
+                    FormValue::File(path) => format!("@{}", path.display()),
+                };
+
+                format!("{}={}", encode(&field.name), encode(&value))
+            })
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+
+    fn multipart(&self, root: &Path) -> std::io::Result<(String, Vec<u8>)> {
+        let boundary = format!(
+            "------------------------quartz{:x}",
+            chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        );
+
+        let mut body = Vec::new();
+
+        for field in &self.fields {
+            body.extend_from_slice(format!("--{boundary}\r\n").as_bytes());
+
+            match &field.value {
+                FormValue::Text(text) => {
+                    body.extend_from_slice(
+                        format!(
+                            "Content-Disposition: form-data; name=\"{}\"\r\n\r\n",
+                            field.name
+                        )
+                        .as_bytes(),
+                    );
+                    body.extend_from_slice(text.as_bytes());
+                }
+                FormValue::File(path) => {
+                    let content = std::fs::read(root.join(path)).map_err(|err| {
+                        std::io::Error::new(err.kind(), format!("{}: {err}", path.display()))
+                    })?;
+
+                    let filename = path
+                        .file_name()
+                        .map(|name| name.to_string_lossy())
+                        .unwrap_or_default();
+
+                    let mime = mime_guess::from_path(path).first_or_octet_stream();
+
+                    body.extend_from_slice(
+                        format!(
+                            "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+                            field.name, filename, mime
+                        )
+                        .as_bytes(),
+                    );
+                    body.extend_from_slice(&content);
+                }
+            }
+
+            body.extend_from_slice(b"\r\n");
+        }
+
+        body.extend_from_slice(format!("--{boundary}--\r\n").as_bytes());
+
+        Ok((format!("multipart/form-data; boundary={boundary}"), body))
+    }
+}
+
+impl Display for Form {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for field in &self.fields {
+            writeln!(f, "{field}")?;
+        }
+
+        Ok(())
+    }
+}
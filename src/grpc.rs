@@ -0,0 +1,167 @@
+use std::path::{Path, PathBuf};
+
+use hyper::body::{Buf, Bytes};
+use prost::Message;
+use prost_reflect::{DynamicMessage, MethodDescriptor};
+use serde::{Deserialize, Serialize};
+
+use crate::{QuartzError, QuartzResult};
+
+/// Describes a gRPC call to be made by an endpoint.
+///
+/// # Example
+///
+/// ```toml
+/// [grpc]
+/// proto = "protos/helloworld.proto"
+/// service = "helloworld.Greeter"
+/// method = "SayHello"
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Grpc {
+    /// Path to the .proto file, relative to the project directory
+    pub proto: PathBuf,
+
+    /// Fully-qualified service name
+    pub service: String,
+
+    /// Method name within the service
+    pub method: String,
+
+    /// Extra directories where imported .proto files are looked up
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<PathBuf>,
+}
+
+impl Grpc {
+    pub const CONTENT_TYPE: &'static str = "application/grpc";
+
+    /// Length of the prefix preceding every message in a gRPC stream: one compression flag byte
+    /// followed by a four bytes big-endian message length.
+    const PREFIX_LEN: usize = 5;
+
+    /// Parses `<SERVICE>/<METHOD>` into the service and method pair.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quartz_cli::grpc::Grpc;
+    ///
+    /// assert_eq!(
+    ///     Grpc::parse_rpc("helloworld.Greeter/SayHello"),
+    ///     Some(("helloworld.Greeter".to_string(), "SayHello".to_string()))
+    /// );
+    /// assert_eq!(Grpc::parse_rpc("/helloworld.Greeter/SayHello/"), Grpc::parse_rpc("helloworld.Greeter/SayHello"));
+    /// assert_eq!(Grpc::parse_rpc("SayHello"), None);
+    /// ```
+    pub fn parse_rpc(input: &str) -> Option<(String, String)> {
+        let (service, method) = input.trim_matches('/').rsplit_once('/')?;
+
+        if service.is_empty() || method.is_empty() {
+            return None;
+        }
+
+        Some((service.to_string(), method.to_string()))
+    }
+
+    /// Request path for this call, as expected by gRPC servers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quartz_cli::grpc::Grpc;
+    ///
+    /// let grpc = Grpc {
+    ///     proto: "helloworld.proto".into(),
+    ///     service: "helloworld.Greeter".into(),
+    ///     method: "SayHello".into(),
+    ///     include: vec![],
+    /// };
+    ///
+    /// assert_eq!(grpc.path(), "/helloworld.Greeter/SayHello");
+    /// ```
+    pub fn path(&self) -> String {
+        format!("/{}/{}", self.service, self.method)
+    }
+
+    /// Compiles the .proto file and finds the descriptor for this call's method.
+    ///
+    /// Relative paths are resolved from `root`.
+    pub fn method_descriptor(&self, root: &Path) -> QuartzResult<MethodDescriptor> {
+        let proto = root.join(&self.proto);
+
+        let mut includes: Vec<PathBuf> = self.include.iter().map(|p| root.join(p)).collect();
+        if let Some(parent) = proto.parent() {
+            includes.push(parent.to_path_buf());
+        }
+
+        let pool = protox::Compiler::new(includes)?
+            .include_imports(true)
+            .open_file(&proto)?
+            .descriptor_pool();
+
+        let service = pool.get_service_by_name(&self.service).unwrap_or_else(|| {
+            panic!(
+                "no service named {} in {}",
+                self.service,
+                self.proto.display()
+            )
+        });
+
+        let method = service
+            .methods()
+            .find(|m| m.name() == self.method)
+            .unwrap_or_else(|| panic!("no method named {} in {}", self.method, self.service));
+
+        if method.is_client_streaming() {
+            panic!("client streaming methods are not supported");
+        }
+
+        Ok(method)
+    }
+
+    /// Encodes a JSON `input` into a length-prefixed message for `method`.
+    pub fn encode(method: &MethodDescriptor, input: &str) -> QuartzResult<Bytes> {
+        let input = if input.trim().is_empty() { "{}" } else { input };
+
+        let mut deserializer = serde_json::Deserializer::from_str(input);
+        let message = DynamicMessage::deserialize(method.input(), &mut deserializer)?;
+        deserializer.end()?;
+
+        let payload = message.encode_to_vec();
+        let mut frame = Vec::with_capacity(Self::PREFIX_LEN + payload.len());
+
+        frame.push(0);
+        frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        frame.extend_from_slice(&payload);
+
+        Ok(frame.into())
+    }
+
+    /// Decodes every length-prefixed message in `data` into pretty JSON strings.
+    pub fn decode(method: &MethodDescriptor, mut data: Bytes) -> QuartzResult<Vec<String>> {
+        let mut messages = Vec::new();
+
+        while data.has_remaining() {
+            if data.remaining() < Self::PREFIX_LEN {
+                return Err(Box::new(QuartzError::Internal));
+            }
+
+            let compressed = data.get_u8();
+            let len = data.get_u32() as usize;
+
+            if compressed != 0 {
+                panic!("compressed gRPC messages are not supported");
+            }
+
+            if data.remaining() < len {
+                return Err(Box::new(QuartzError::Internal));
+            }
+
+            let message = DynamicMessage::decode(method.output(), data.split_to(len))?;
+            messages.push(serde_json::to_string_pretty(&message)?);
+        }
+
+        Ok(messages)
+    }
+}
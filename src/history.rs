@@ -1,4 +1,4 @@
-use crate::{snippet, Ctx, QuartzError, QuartzResult};
+use crate::{redact::Redactor, snippet, Ctx, QuartzError, QuartzResult};
 use std::fmt::Display;
 use std::io::Write;
 use std::path::{Path, PathBuf};
@@ -19,6 +19,7 @@ pub struct EntryBuilder {
     timestemp: i64,
     handle: Option<String>,
     messages: Vec<String>,
+    redactor: Redactor,
 }
 
 pub struct History {
@@ -76,6 +77,7 @@ impl History {
         std::fs::OpenOptions::new()
             .create(true)
             .write(true)
+            .truncate(true)
             .open(History::dir(ctx).join(entry.timestemp.to_string()))?
             .write_all(content.as_bytes())?;
 
@@ -97,12 +99,12 @@ impl EntryBuilder {
         T: Into<snippet::Http>,
     {
         let m: snippet::Http = value.into();
-        self.messages.push(m.to_string());
+        self.messages.push(self.redactor.head(&m));
         self
     }
 
     pub fn message_raw(&mut self, value: String) -> &mut Self {
-        self.messages.push(value);
+        self.messages.push(self.redactor.text(&value));
         self
     }
 
@@ -111,6 +113,12 @@ impl EntryBuilder {
         self
     }
 
+    /// Hides sensitive headers and values of messages added afterwards.
+    pub fn redactor(&mut self, value: Redactor) -> &mut Self {
+        self.redactor = value;
+        self
+    }
+
     pub fn build(self) -> QuartzResult<Entry, QuartzError> {
         let handle = self.handle.ok_or(QuartzError::Internal)?;
 
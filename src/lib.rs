@@ -1,12 +1,24 @@
 pub mod action;
+pub mod auth;
+pub mod bench;
+pub mod builtin;
 pub mod cli;
+pub mod client;
+pub mod compression;
 pub mod config;
 pub mod cookie;
+pub mod dotenv;
 pub mod endpoint;
 pub mod env;
+pub mod form;
+pub mod grpc;
 pub mod history;
+pub mod redact;
+pub mod redirect;
+pub mod secret;
 pub mod snippet;
 pub mod state;
+pub mod template;
 pub mod tree;
 pub mod validator;
 
@@ -156,7 +168,7 @@ impl Ctx {
 
         if self.args.early_apply_environment {
             let env = self.require_env();
-            endpoint.apply_env(&env);
+            endpoint.apply_env(&env.scoped(&endpoint, &Default::default()));
         }
 
         endpoint
@@ -174,7 +186,7 @@ impl Ctx {
             .unwrap_or("default".into());
 
         Env::parse(self, &state)
-            .unwrap_or_else(|_| panic!("could not resolve {} environment", state.red()))
+            .unwrap_or_else(|err| panic!("could not resolve {} environment: {err}", state.red()))
     }
 
     /// Opens an editor to modified the specified file at `path` in a temporary file.
@@ -266,12 +278,18 @@ impl Ctx {
                 panic!("failed to open pager: {}\n\n{}", pager, err);
             });
 
-        child.stdin.as_mut().unwrap().write_all(input)?;
+        let written = child.stdin.as_mut().unwrap().write_all(input);
         child.wait()?;
+        written?;
 
         Ok(())
     }
 
+    /// Redactor of configured headers, for output written to history or snippets.
+    pub fn redactor(&self) -> redact::Redactor {
+        redact::Redactor::new(&self.config.preferences.redact())
+    }
+
     pub fn user_agent() -> String {
         let mut agent = String::from("quartz/");
         agent.push_str(Ctx::VERSION);
@@ -283,6 +301,11 @@ impl Ctx {
         self.path.as_ref()
     }
 
+    /// Directory that holds the `.quartz` folder.
+    pub fn project_dir(&self) -> &Path {
+        self.path.parent().unwrap_or(&self.path)
+    }
+
     pub fn code(&mut self, value: ExitCode) {
         self.code = value;
     }
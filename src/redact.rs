@@ -0,0 +1,102 @@
+use crate::secret::{self, MASK};
+
+/// Headers redacted unless configured otherwise.
+pub const DEFAULT_HEADERS: [&str; 4] = ["Authorization", "Cookie", "Set-Cookie", "X-Api-Key"];
+
+/// Hides sensitive headers and values from what is written to history or printed as snippets.
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+    /// Names of headers whose values are hidden, in any case
+    headers: Vec<String>,
+
+    /// Values hidden wherever they appear, such as secret variables
+    values: Vec<String>,
+}
+
+impl Redactor {
+    pub fn new<T>(headers: &[T]) -> Self
+    where
+        T: AsRef<str>,
+    {
+        Self {
+            headers: headers.iter().map(|h| h.as_ref().to_string()).collect(),
+            values: Vec::new(),
+        }
+    }
+
+    pub fn values(&mut self, values: Vec<String>) -> &mut Self {
+        self.values = values;
+        self
+    }
+
+    pub fn is_sensitive(&self, header: &str) -> bool {
+        self.headers.iter().any(|h| h.eq_ignore_ascii_case(header))
+    }
+
+    /// Value of a header as it can be shown.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quartz_cli::redact::{Redactor, DEFAULT_HEADERS};
+    ///
+    /// let mut redactor = Redactor::new(&DEFAULT_HEADERS);
+    /// redactor.values(vec!["s3cr3t".to_string()]);
+    ///
+    /// assert_eq!(redactor.header("authorization", "Bearer abc"), "****");
+    /// assert_eq!(redactor.header("X-Token", "s3cr3t"), "****");
+    /// assert_eq!(redactor.header("Accept", "*/*"), "*/*");
+    /// ```
+    pub fn header(&self, name: &str, value: &str) -> String {
+        if self.is_sensitive(name) {
+            MASK.to_string()
+        } else {
+            self.text(value)
+        }
+    }
+
+    /// Text with every sensitive value hidden.
+    pub fn text(&self, input: &str) -> String {
+        secret::mask(input, &self.values)
+    }
+
+    /// Redacts an HTTP message head, written with one `name: value` header per line as in
+    /// [`crate::snippet::Http`]. Lines may be prefixed by `>` or `<`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quartz_cli::redact::{Redactor, DEFAULT_HEADERS};
+    ///
+    /// let redactor = Redactor::new(&DEFAULT_HEADERS);
+    /// let head = "> GET / HTTP/1.1\n> cookie: session=1\n> accept: */*\n>";
+    ///
+    /// assert_eq!(
+    ///     redactor.head(head),
+    ///     "> GET / HTTP/1.1\n> cookie: ****\n> accept: */*\n>"
+    /// );
+    /// ```
+    pub fn head(&self, input: &str) -> String {
+        let lines: Vec<String> = input
+            .split('\n')
+            .map(|line| {
+                let (prefix, header) = match line.split_once(['>', '<']) {
+                    Some(("", header)) => (&line[..line.len() - header.len()], header),
+                    _ => ("", line),
+                };
+
+                match header.split_once(": ") {
+                    Some((name, value)) => {
+                        let indent = &header[..header.len() - header.trim_start().len()];
+                        let name = name.trim_start();
+
+                        format!("{prefix}{indent}{name}: {}", self.header(name, value))
+                    }
+                    None => self.text(line),
+                }
+            })
+            .collect();
+
+        lines.join("\n")
+    }
+}
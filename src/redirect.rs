@@ -0,0 +1,163 @@
+use hyper::{Method, StatusCode, Uri};
+
+/// Default limit of redirects followed by a single request.
+pub const MAX_REDIRECTS: usize = 20;
+
+/// Resolves a Location header value against the URL of the request that received it, as
+/// described by RFC 3986.
+///
+/// # Examples
+///
+/// ```
+/// use hyper::Uri;
+/// use quartz_cli::redirect::resolve;
+///
+/// let base = Uri::from_static("http://localhost:8080/v1/users/me?page=2");
+///
+/// assert_eq!(resolve(&base, "https://example.com/").unwrap(), "https://example.com/");
+/// assert_eq!(resolve(&base, "//example.com/a").unwrap(), "http://example.com/a");
+/// assert_eq!(resolve(&base, "/login?next=1").unwrap(), "http://localhost:8080/login?next=1");
+/// assert_eq!(resolve(&base, "profile").unwrap(), "http://localhost:8080/v1/users/profile");
+/// assert_eq!(resolve(&base, "../teams/./1#members").unwrap(), "http://localhost:8080/v1/teams/1");
+/// assert_eq!(resolve(&base, "?page=3").unwrap(), "http://localhost:8080/v1/users/me?page=3");
+/// assert!(resolve(&base, "http://exa mple.com").is_none());
+/// ```
+pub fn resolve(base: &Uri, location: &str) -> Option<Uri> {
+    let location = location.split('#').next().unwrap_or_default();
+
+    if has_scheme(location) {
+        return location.parse().ok();
+    }
+
+    let scheme = base.scheme_str().unwrap_or("http");
+
+    if location.starts_with("//") {
+        return format!("{scheme}:{location}").parse().ok();
+    }
+
+    let authority = base.authority()?;
+    let (path, query) = match location.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (location, None),
+    };
+
+    let path = if path.starts_with('/') {
+        remove_dot_segments(path)
+    } else if path.is_empty() {
+        base.path().to_string()
+    } else {
+        let base_path = base.path();
+        let dir = &base_path[..base_path.rfind('/').map_or(0, |i| i + 1)];
+
+        remove_dot_segments(&format!("/{}{path}", dir.trim_start_matches('/')))
+    };
+
+    let query = match query {
+        Some(query) => Some(query),
+        // Empty references keep the base query
+        None if location.is_empty() => base.query(),
+        None => None,
+    };
+
+    let path_and_query = match query {
+        Some(query) => format!("{path}?{query}"),
+        None => path,
+    };
+
+    format!("{scheme}://{authority}{path_and_query}")
+        .parse()
+        .ok()
+}
+
+fn has_scheme(input: &str) -> bool {
+    match input.split_once(':') {
+        Some((scheme, _)) => {
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        }
+        None => false,
+    }
+}
+
+fn remove_dot_segments(path: &str) -> String {
+    let mut output: Vec<&str> = Vec::new();
+    let segments: Vec<&str> = path.split('/').skip(1).collect();
+
+    for (i, segment) in segments.iter().enumerate() {
+        let is_last = i == segments.len() - 1;
+
+        match *segment {
+            "." => {
+                if is_last {
+                    output.push("");
+                }
+            }
+            ".." => {
+                output.pop();
+                if is_last {
+                    output.push("");
+                }
+            }
+            segment => output.push(segment),
+        }
+    }
+
+    format!("/{}", output.join("/"))
+}
+
+/// Method used to follow a redirect, as described by RFC 9110. Returns [`None`] when the
+/// original method and body are kept.
+///
+/// # Examples
+///
+/// ```
+/// use hyper::{Method, StatusCode};
+/// use quartz_cli::redirect::method_after;
+///
+/// assert_eq!(method_after(StatusCode::FOUND, &Method::POST), Some(Method::GET));
+/// assert_eq!(method_after(StatusCode::SEE_OTHER, &Method::PUT), Some(Method::GET));
+/// assert_eq!(method_after(StatusCode::SEE_OTHER, &Method::HEAD), None);
+/// assert_eq!(method_after(StatusCode::TEMPORARY_REDIRECT, &Method::POST), None);
+/// assert_eq!(method_after(StatusCode::MOVED_PERMANENTLY, &Method::PUT), None);
+/// ```
+pub fn method_after(status: StatusCode, method: &Method) -> Option<Method> {
+    match status {
+        StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND if method == Method::POST => {
+            Some(Method::GET)
+        }
+        StatusCode::SEE_OTHER if method != Method::GET && method != Method::HEAD => {
+            Some(Method::GET)
+        }
+        _ => None,
+    }
+}
+
+/// Whether both URLs share scheme, host and port.
+///
+/// # Examples
+///
+/// ```
+/// use hyper::Uri;
+/// use quartz_cli::redirect::same_origin;
+///
+/// let a = Uri::from_static("http://localhost/a");
+///
+/// assert!(same_origin(&a, &Uri::from_static("http://localhost:80/b")));
+/// assert!(!same_origin(&a, &Uri::from_static("https://localhost/a")));
+/// assert!(!same_origin(&a, &Uri::from_static("http://localhost:8080/a")));
+/// assert!(!same_origin(&a, &Uri::from_static("http://example.com/a")));
+/// ```
+pub fn same_origin(a: &Uri, b: &Uri) -> bool {
+    let port = |uri: &Uri| {
+        uri.port_u16().or(match uri.scheme_str() {
+            Some("https") => Some(443),
+            _ => Some(80),
+        })
+    };
+
+    a.scheme_str() == b.scheme_str()
+        && a.host().map(str::to_ascii_lowercase) == b.host().map(str::to_ascii_lowercase)
+        && port(a) == port(b)
+}
@@ -0,0 +1,247 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::sync::OnceLock;
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use openssl::{
+    hash::MessageDigest,
+    pkcs5::pbkdf2_hmac,
+    symm::{decrypt_aead, encrypt_aead, Cipher},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::QuartzResult;
+
+/// Text shown in place of secret values.
+pub const MASK: &str = "****";
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+const ITERATIONS: usize = 100_000;
+
+/// Plaintext encrypted with the key to tell whether later passphrases match it.
+const CHECK: &[u8] = b"quartz";
+
+/// Key derived from the passphrase of a [`Secrets`] store.
+pub struct Key([u8; 32]);
+
+impl Key {
+    /// Derives a key with PBKDF2-HMAC-SHA256.
+    pub fn derive(passphrase: &str, salt: &[u8]) -> QuartzResult<Self> {
+        let mut key = [0; 32];
+        pbkdf2_hmac(
+            passphrase.as_bytes(),
+            salt,
+            ITERATIONS,
+            MessageDigest::sha256(),
+            &mut key,
+        )?;
+
+        Ok(Self(key))
+    }
+
+    /// Encrypts `plaintext` with AES-256-GCM, binding it to `name`. Returns the nonce,
+    /// ciphertext and tag encoded as Base64.
+    pub fn encrypt(&self, name: &str, plaintext: &[u8]) -> QuartzResult<String> {
+        let mut nonce = [0; NONCE_LEN];
+        openssl::rand::rand_bytes(&mut nonce)?;
+
+        let mut tag = [0; TAG_LEN];
+        let ciphertext = encrypt_aead(
+            Cipher::aes_256_gcm(),
+            &self.0,
+            Some(&nonce),
+            name.as_bytes(),
+            plaintext,
+            &mut tag,
+        )?;
+
+        Ok(STANDARD.encode([&nonce[..], &ciphertext, &tag].concat()))
+    }
+
+    /// Reverts [`Key::encrypt`], failing if the value was changed or encrypted with another key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quartz_cli::secret::Key;
+    ///
+    /// let key = Key::derive("passphrase", b"salt").unwrap();
+    /// let value = key.encrypt("token", b"s3cr3t").unwrap();
+    ///
+    /// assert_eq!(key.decrypt("token", &value).unwrap(), b"s3cr3t");
+    /// assert!(key.decrypt("other", &value).is_err());
+    ///
+    /// let wrong = Key::derive("wrong", b"salt").unwrap();
+    /// assert!(wrong.decrypt("token", &value).is_err());
+    /// ```
+    pub fn decrypt(&self, name: &str, value: &str) -> QuartzResult<Vec<u8>> {
+        let bytes = STANDARD.decode(value)?;
+
+        if bytes.len() < NONCE_LEN + TAG_LEN {
+            return Err("malformed secret".into());
+        }
+
+        let (nonce, rest) = bytes.split_at(NONCE_LEN);
+        let (ciphertext, tag) = rest.split_at(rest.len() - TAG_LEN);
+
+        Ok(decrypt_aead(
+            Cipher::aes_256_gcm(),
+            &self.0,
+            Some(nonce),
+            name.as_bytes(),
+            ciphertext,
+            tag,
+        )?)
+    }
+}
+
+/// Encrypted variables of an environment. Names are kept in plain text, so they can be listed
+/// without the passphrase.
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct Secrets {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    salt: String,
+
+    /// [`CHECK`] encrypted with the key
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    check: String,
+
+    #[serde(default)]
+    values: BTreeMap<String, String>,
+}
+
+impl Secrets {
+    pub const FILENAME: &'static str = "secrets.enc";
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.values.contains_key(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        self.values.keys()
+    }
+
+    /// Key of this store, made from the passphrase given by [`passphrase`]. A new store takes
+    /// the key of the first passphrase given.
+    ///
+    /// # Panics
+    ///
+    /// Program is terminated if the passphrase does not match the one used before.
+    pub fn key(&mut self) -> QuartzResult<Key> {
+        let passphrase = passphrase()?;
+
+        if self.salt.is_empty() {
+            let mut salt = [0; SALT_LEN];
+            openssl::rand::rand_bytes(&mut salt)?;
+
+            let key = Key::derive(&passphrase, &salt)?;
+            self.salt = STANDARD.encode(salt);
+            self.check = key.encrypt("", CHECK)?;
+
+            return Ok(key);
+        }
+
+        let key = Key::derive(&passphrase, &STANDARD.decode(&self.salt)?)?;
+        if key.decrypt("", &self.check).ok().as_deref() != Some(CHECK) {
+            panic!("wrong passphrase for secrets");
+        }
+
+        Ok(key)
+    }
+
+    pub fn set(&mut self, key: &Key, name: &str, value: &str) -> QuartzResult {
+        let value = key.encrypt(name, value.as_bytes())?;
+        self.values.insert(name.to_string(), value);
+
+        Ok(())
+    }
+
+    pub fn get(&self, key: &Key, name: &str) -> QuartzResult<Option<String>> {
+        match self.values.get(name) {
+            Some(value) => Ok(Some(String::from_utf8(key.decrypt(name, value)?)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.values.remove(name).is_some()
+    }
+
+    /// Decrypts every secret into `(name, value)` pairs.
+    pub fn decrypt(&mut self) -> QuartzResult<Vec<(String, String)>> {
+        let key = self.key()?;
+
+        self.values
+            .keys()
+            .map(|name| Ok((name.clone(), self.get(&key, name)?.unwrap_or_default())))
+            .collect()
+    }
+}
+
+/// Passphrase of secret stores, read from a key file set by `QUARTZ_SECRET_KEY_FILE`, the
+/// `QUARTZ_SECRET_PASSPHRASE` variable or the terminal, in this order.
+///
+/// # Panics
+///
+/// Program is terminated if none of them is available.
+pub fn passphrase() -> QuartzResult<String> {
+    // Environments extending others may unlock many stores, asking only once
+    static PASSPHRASE: OnceLock<String> = OnceLock::new();
+
+    if let Some(passphrase) = PASSPHRASE.get() {
+        return Ok(passphrase.clone());
+    }
+
+    let passphrase = read_passphrase()?;
+    Ok(PASSPHRASE.get_or_init(|| passphrase).clone())
+}
+
+fn read_passphrase() -> QuartzResult<String> {
+    if let Ok(path) = std::env::var("QUARTZ_SECRET_KEY_FILE") {
+        let content = std::fs::read_to_string(&path)
+            .unwrap_or_else(|err| panic!("failed to read key file {path}: {err}"));
+
+        return Ok(content.trim().to_string());
+    }
+
+    if let Ok(passphrase) = std::env::var("QUARTZ_SECRET_PASSPHRASE") {
+        return Ok(passphrase);
+    }
+
+    let term = console::Term::stderr();
+    if !term.is_term() {
+        panic!("missing passphrase for secrets. Set QUARTZ_SECRET_PASSPHRASE or QUARTZ_SECRET_KEY_FILE");
+    }
+
+    eprint!("Passphrase: ");
+    std::io::stderr().flush()?;
+
+    Ok(term.read_secure_line()?)
+}
+
+/// Replaces every occurrence of `values` in `input` with [`MASK`].
+///
+/// # Examples
+///
+/// ```
+/// use quartz_cli::secret::mask;
+///
+/// let values = vec!["s3cr3t".to_string(), String::new()];
+///
+/// assert_eq!(mask("Bearer s3cr3t", &values), "Bearer ****");
+/// assert_eq!(mask("nothing here", &values), "nothing here");
+/// ```
+pub fn mask(input: &str, values: &[String]) -> String {
+    values
+        .iter()
+        .filter(|value| !value.is_empty())
+        .fold(input.to_string(), |output, value| {
+            output.replace(value, MASK)
+        })
+}
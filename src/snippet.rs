@@ -1,13 +1,15 @@
 use std::ops::Deref;
 
-use crate::{Endpoint, QuartzResult};
-use hyper::{Body, Request, Response};
+use crate::{form::FormEncoding, redact::Redactor, Endpoint, QuartzResult};
+use hyper::{Request, Response};
 
 enum CurlOption {
     Location,
     Request,
     Header,
     Data,
+    DataUrlencode,
+    Form,
 }
 
 #[derive(clap::Args, Debug)]
@@ -22,7 +24,7 @@ pub struct Curl {
 }
 
 impl Curl {
-    pub fn print(&self, endpoint: &mut Endpoint) -> QuartzResult {
+    pub fn print(&self, endpoint: &mut Endpoint, redactor: &Redactor) -> QuartzResult {
         let separator = if self.multiline { " \\\n\t" } else { " " };
 
         print!(
@@ -42,11 +44,26 @@ impl Curl {
                 separator,
                 self.option_string(CurlOption::Header),
                 key,
-                value
+                redactor.header(key, value)
             );
         }
 
-        if let Some(body) = endpoint.body() {
+        if let Some(form) = endpoint.form() {
+            let option = match form.encoding {
+                FormEncoding::Multipart => CurlOption::Form,
+                FormEncoding::UrlEncoded => CurlOption::DataUrlencode,
+            };
+            let option = self.option_string(option);
+
+            for field in &form.fields {
+                print!(
+                    "{separator}{option} '{}'",
+                    redactor.text(&field.to_string())
+                );
+            }
+
+            println!();
+        } else if let Some(body) = endpoint.body() {
             let mut body = body.to_owned();
             print!("{}{} '", separator, self.option_string(CurlOption::Data));
 
@@ -54,7 +71,7 @@ impl Curl {
                 body.truncate(body.len() - 1);
             }
 
-            print!("{body}");
+            print!("{}", redactor.text(&body));
             println!("'");
         } else {
             println!();
@@ -93,6 +110,14 @@ impl Curl {
                     "-d"
                 }
             }
+            CurlOption::DataUrlencode => "--data-urlencode",
+            CurlOption::Form => {
+                if self.long {
+                    "--form"
+                } else {
+                    "-F"
+                }
+            }
         };
 
         result.to_string()
@@ -109,8 +134,8 @@ impl Deref for Http {
     }
 }
 
-impl From<&Response<Body>> for Http {
-    fn from(value: &Response<Body>) -> Self {
+impl<T> From<&Response<T>> for Http {
+    fn from(value: &Response<T>) -> Self {
         let mut output = String::new();
 
         output.push_str(&format!("< {:?}", value.version()));
@@ -131,8 +156,8 @@ impl From<&Response<Body>> for Http {
     }
 }
 
-impl From<&Request<Body>> for Http {
-    fn from(value: &Request<Body>) -> Self {
+impl<T> From<&Request<T>> for Http {
+    fn from(value: &Request<T>) -> Self {
         let mut output = String::new();
 
         output.push_str(&format!(
@@ -158,17 +183,20 @@ impl From<&Request<Body>> for Http {
 }
 
 impl Http {
-    pub fn print(endpoint: &mut Endpoint) -> QuartzResult {
+    pub fn print(endpoint: &mut Endpoint, redactor: &Redactor) -> QuartzResult {
         let url = endpoint.full_url()?;
         let path = url.path_and_query().unwrap();
 
         println!("{} {} HTTP/1.1", endpoint.method, path.as_str());
         println!("Host: {}", url.host().unwrap());
-        print!("{}", endpoint.headers);
+
+        for (key, value) in endpoint.headers.iter() {
+            println!("{key}: {}", redactor.header(key, value));
+        }
 
         if let Some(body) = endpoint.body() {
             println!();
-            print!("{body}");
+            print!("{}", redactor.text(body));
         }
 
         Ok(())
@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+use crate::builtin;
+
+/// Characters escaped by the `urlencode` filter: all but RFC 3986 unreserved ones.
+const COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// How deep variables may reference other variables.
+const MAX_DEPTH: usize = 32;
+
+/// Renders `{{ ... }}` tags of `input` with `variables`.
+///
+/// A tag holds a variable name, a built-in variable such as `$uuid` or a quoted literal,
+/// followed by filters separated by `|`. Values of variables are rendered too, so they may
+/// reference each other. Tags that cannot be resolved are kept as is.
+///
+/// Available filters are `default: VALUE`, used when the value is unknown or empty,
+/// `urlencode`, `base64`, `json`, `upper`, `lower` and `trim`.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use quartz_cli::template::render;
+///
+/// let variables = HashMap::from([
+///     ("host".to_string(), "localhost".to_string()),
+///     ("url".to_string(), "http://{{host}}/v1".to_string()),
+///     ("name".to_string(), "Jane \"J\" Doe".to_string()),
+/// ]);
+///
+/// assert_eq!(render("{{ url }}/users", &variables), "http://localhost/v1/users");
+/// assert_eq!(render("{{id | default: 1}}", &variables), "1");
+/// assert_eq!(render("{{ name | json }}", &variables), r#""Jane \"J\" Doe""#);
+/// assert_eq!(render("?q={{name | urlencode}}", &variables), "?q=Jane%20%22J%22%20Doe");
+/// assert_eq!(render("{{host | upper}}", &variables), "LOCALHOST");
+/// assert_eq!(render("{{$base64 {{host}}}}", &variables), "bG9jYWxob3N0");
+/// assert_eq!(render("{{ unknown }}", &variables), "{{ unknown }}");
+/// ```
+pub fn render(input: &str, variables: &HashMap<String, String>) -> String {
+    Renderer {
+        variables,
+        stack: Vec::new(),
+    }
+    .render(input)
+}
+
+/// Names in `{{ ... }}` tags of `input`, such as the ones left by [`render`] for unresolved
+/// variables. Tags that do not look like variables are ignored.
+///
+/// # Examples
+///
+/// ```
+/// use quartz_cli::template::placeholders;
+///
+/// assert_eq!(
+///     placeholders("/users/{{ id }}?token={{$env.TOKEN}}&q={{query | urlencode}}"),
+///     vec!["id", "$env.TOKEN", "query"]
+/// );
+/// assert!(placeholders("{{#each items}}{{/each}} {\"a\":{\"b\":1}}").is_empty());
+/// ```
+pub fn placeholders(input: &str) -> Vec<String> {
+    let mut list = Vec::new();
+    let mut rest = input;
+
+    while let Some(start) = rest.find("{{") {
+        let content = &rest[start + 2..];
+        let Some(len) = closing(content) else {
+            break;
+        };
+
+        let term = split(&content[..len], '|')[0].trim();
+        let is_variable = term.starts_with('$')
+            || (term.starts_with(|c: char| c.is_alphabetic() || c == '_')
+                && term
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.')));
+
+        if is_variable {
+            list.push(term.to_string());
+        }
+
+        rest = &content[len + 2..];
+    }
+
+    list
+}
+
+struct Renderer<'a> {
+    variables: &'a HashMap<String, String>,
+
+    /// Variables being rendered, to catch the ones referencing themselves
+    stack: Vec<&'a str>,
+}
+
+impl<'a> Renderer<'a> {
+    fn render(&mut self, input: &str) -> String {
+        let mut output = String::with_capacity(input.len());
+        let mut rest = input;
+
+        while let Some(start) = rest.find("{{") {
+            output.push_str(&rest[..start]);
+
+            let content = &rest[start + 2..];
+            let Some(len) = closing(content) else {
+                rest = &rest[start..];
+                break;
+            };
+
+            match self.evaluate(&content[..len]) {
+                Some(value) => output.push_str(&value),
+                None => output.push_str(&rest[start..start + len + 4]),
+            }
+
+            rest = &content[len + 2..];
+        }
+
+        output.push_str(rest);
+        output
+    }
+
+    /// Value of a tag content, or [`None`] when unresolved.
+    fn evaluate(&mut self, content: &str) -> Option<String> {
+        let mut parts = split(content, '|').into_iter();
+        let term = parts.next().unwrap_or_default().trim();
+
+        let mut value = if let Some(literal) = unquote(term) {
+            Some(literal.to_string())
+        } else if let Some(expr) = term.strip_prefix('$') {
+            builtin::generate(&self.render(expr))
+        } else {
+            self.variable(term)
+        };
+
+        for filter in parts {
+            let (name, arg) = match filter.split_once(':') {
+                Some((name, arg)) => (name.trim(), Some(arg.trim())),
+                None => (filter.trim(), None),
+            };
+
+            value = match name {
+                "default" => match value.filter(|v| !v.is_empty()) {
+                    Some(value) => Some(value),
+                    None => {
+                        let arg = arg.unwrap_or_default();
+                        Some(self.render(unquote(arg).unwrap_or(arg)))
+                    }
+                },
+                _ => value.map(|v| apply_filter(name, &v)),
+            };
+        }
+
+        value
+    }
+
+    fn variable(&mut self, name: &str) -> Option<String> {
+        let (key, value) = self.variables.get_key_value(name)?;
+
+        if self.stack.contains(&key.as_str()) || self.stack.len() >= MAX_DEPTH {
+            panic!("variable {name} references itself");
+        }
+
+        self.stack.push(key);
+        let value = self.render(value);
+        self.stack.pop();
+
+        Some(value)
+    }
+}
+
+fn apply_filter(name: &str, value: &str) -> String {
+    match name {
+        "urlencode" => utf8_percent_encode(value, COMPONENT).to_string(),
+        "base64" => STANDARD.encode(value),
+        "json" => serde_json::to_string(value).unwrap_or_default(),
+        "upper" => value.to_uppercase(),
+        "lower" => value.to_lowercase(),
+        "trim" => value.trim().to_string(),
+        _ => panic!("unknown template filter: {name}"),
+    }
+}
+
+/// Length of a tag content, up to the `}}` matching an already open `{{`.
+fn closing(content: &str) -> Option<usize> {
+    let bytes = content.as_bytes();
+    let mut depth = 1;
+    let mut i = 0;
+
+    while i + 1 < bytes.len() {
+        match &bytes[i..i + 2] {
+            b"{{" => {
+                depth += 1;
+                i += 2;
+            }
+            b"}}" => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+                i += 2;
+            }
+            _ => i += 1,
+        }
+    }
+
+    None
+}
+
+/// Splits `input` by `delimiter`, except within quotes or nested tags.
+fn split(input: &str, delimiter: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut quoted = false;
+    let mut start = 0;
+    let mut chars = input.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => quoted = !quoted,
+            '{' if chars.peek().is_some_and(|(_, next)| *next == '{') => {
+                chars.next();
+                depth += 1;
+            }
+            '}' if depth > 0 && chars.peek().is_some_and(|(_, next)| *next == '}') => {
+                chars.next();
+                depth -= 1;
+            }
+            c if c == delimiter && depth == 0 && !quoted => {
+                parts.push(&input[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => (),
+        }
+    }
+
+    parts.push(&input[start..]);
+    parts
+}
+
+fn unquote(input: &str) -> Option<&str> {
+    input
+        .strip_prefix('"')
+        .and_then(|input| input.strip_suffix('"'))
+}
@@ -0,0 +1,455 @@
+use hyper::{Body, Response};
+use quartz_cli::auth::DigestChallenge;
+
+use crate::utils::*;
+
+/// Appends `content` to the endpoint.toml file of `handle`.
+fn append_endpoint(quartz: &Quartz, handle: &str, content: &str) -> TestResult {
+    let path = quartz
+        .dir()
+        .join("endpoints")
+        .join(handle)
+        .join("endpoint.toml");
+    let current = std::fs::read_to_string(&path)?;
+    std::fs::write(&path, format!("{current}\n{content}"))?;
+
+    Ok(())
+}
+
+fn authorization(server: &Server) -> Option<String> {
+    server.requests()[0]
+        .headers()
+        .get("authorization")
+        .map(|v| v.to_str().unwrap().to_string())
+}
+
+#[test]
+fn it_sends_basic_auth() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "sample", "--url", &server.url("/"), "--use"])?;
+    quartz.cmd(&["var", "set", "password=open sesame"])?;
+    append_endpoint(
+        &quartz,
+        "sample",
+        "[auth]\ntype = \"basic\"\nuser = \"Aladdin\"\npassword = \"{{password}}\"\n",
+    )?;
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(
+        authorization(&server).as_deref(),
+        Some("Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_inherits_auth_from_parent() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "api", "--url", &server.url("/")])?;
+    quartz.cmd(&["create", "api/users", "--url", "**/users", "--use"])?;
+    append_endpoint(
+        &quartz,
+        "api",
+        "[auth]\ntype = \"bearer\"\ntoken = \"abc\"\n",
+    )?;
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(authorization(&server).as_deref(), Some("Bearer abc"));
+
+    append_endpoint(&quartz, "api/users", "[auth]\ntype = \"none\"\n")?;
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(authorization(&server), None);
+
+    Ok(())
+}
+
+#[test]
+fn it_inherits_auth_across_empty_handles() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "api", "--url", &server.url("/")])?;
+    quartz.cmd(&[
+        "create",
+        "api/users/list",
+        "--url",
+        &server.url("/users"),
+        "--use",
+    ])?;
+    assert!(!quartz
+        .dir()
+        .join("endpoints/api/users/endpoint.toml")
+        .exists());
+
+    append_endpoint(
+        &quartz,
+        "api",
+        "[auth]\ntype = \"bearer\"\ntoken = \"abc\"\n",
+    )?;
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(authorization(&server).as_deref(), Some("Bearer abc"));
+
+    Ok(())
+}
+
+#[test]
+fn it_uses_environment_auth() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_using_default_env()?;
+
+    quartz.cmd(&["create", "sample", "--url", &server.url("/"), "--use"])?;
+    std::fs::write(
+        quartz.dir().join("env/default/env.toml"),
+        "[auth]\ntype = \"bearer\"\ntoken = \"from-env\"\n",
+    )?;
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(authorization(&server).as_deref(), Some("Bearer from-env"));
+
+    // Explicit headers take precedence
+    let output = quartz.cmd(&["send", "-H", "Authorization: Bearer header"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(authorization(&server).as_deref(), Some("Bearer header"));
+
+    Ok(())
+}
+
+#[test]
+fn it_answers_digest_challenge() -> TestResult {
+    const CHALLENGE: &str = r#"Digest realm="api", qop="auth", nonce="abc123", opaque="xyz""#;
+
+    let server = Server::new(|req| {
+        let authorized = req
+            .headers()
+            .get("authorization")
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|header| {
+                let cnonce = header
+                    .split("cnonce=\"")
+                    .nth(1)
+                    .and_then(|rest| rest.split('"').next())
+                    .unwrap_or_default();
+
+                let expected = DigestChallenge::parse(CHALLENGE)
+                    .unwrap()
+                    .answer("Mufasa", "Circle Of Life", "GET", "/dir?a=1", cnonce)
+                    .unwrap();
+
+                header == expected
+            });
+
+        if authorized {
+            Response::new(Body::from("welcome"))
+        } else {
+            Response::builder()
+                .status(401)
+                .header("WWW-Authenticate", CHALLENGE)
+                .body(Body::empty())
+                .unwrap()
+        }
+    });
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&[
+        "create",
+        "sample",
+        "--url",
+        &server.url("/dir?a=1"),
+        "--use",
+    ])?;
+    append_endpoint(
+        &quartz,
+        "sample",
+        "[auth]\ntype = \"digest\"\nuser = \"Mufasa\"\npassword = \"Circle Of Life\"\n",
+    )?;
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(output.stdout, "welcome");
+    assert_eq!(server.requests().len(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn it_answers_digest_among_many_challenges() -> TestResult {
+    let server = Server::new(|req| {
+        let is_digest = req
+            .headers()
+            .get("authorization")
+            .is_some_and(|header| header.as_bytes().starts_with(b"Digest "));
+
+        if is_digest {
+            Response::new(Body::from("welcome"))
+        } else {
+            Response::builder()
+                .status(401)
+                .header("WWW-Authenticate", r#"Basic realm="api""#)
+                .header("WWW-Authenticate", r#"Digest realm="api", nonce="abc123""#)
+                .body(Body::empty())
+                .unwrap()
+        }
+    });
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "sample", "--url", &server.url("/"), "--use"])?;
+    append_endpoint(
+        &quartz,
+        "sample",
+        "[auth]\ntype = \"digest\"\nuser = \"Mufasa\"\npassword = \"Circle Of Life\"\n",
+    )?;
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(output.stdout, "welcome");
+
+    Ok(())
+}
+
+/// Authorization server answering tokens at "/token", numbered by how many were issued.
+fn oauth_server(expires_in: u64) -> Server {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let issued = AtomicUsize::new(0);
+
+    Server::new(move |req| {
+        if req.uri().path() != "/token" {
+            return Response::new(Body::empty());
+        }
+
+        let n = issued.fetch_add(1, Ordering::SeqCst) + 1;
+
+        Response::new(Body::from(format!(
+            r#"{{ "access_token": "token-{n}", "refresh_token": "refresh-{n}", "expires_in": {expires_in} }}"#
+        )))
+    })
+}
+
+fn use_oauth(quartz: &Quartz, server: &Server) -> TestResult {
+    quartz.cmd(&["create", "sample", "--url", &server.url("/api"), "--use"])?;
+    quartz.cmd(&["var", "set", "secret=s3cr3t"])?;
+
+    std::fs::write(
+        quartz.dir().join("env/default/env.toml"),
+        format!(
+            "[auth]\ntype = \"oauth2\"\ngrant = \"client_credentials\"\ntoken_url = \"{}\"\nclient_id = \"quartz\"\nclient_secret = \"{{{{secret}}}}\"\n",
+            server.url("/token")
+        ),
+    )?;
+
+    Ok(())
+}
+
+#[test]
+fn it_fetches_and_caches_oauth2_token() -> TestResult {
+    let server = oauth_server(3600);
+    let quartz = Quartz::preset_using_default_env()?;
+    use_oauth(&quartz, &server)?;
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(
+        requests[0].body(),
+        "grant_type=client_credentials&client_id=quartz&client_secret=s3cr3t"
+    );
+    assert_eq!(requests[1].headers()["authorization"], "Bearer token-1");
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1, "token was not cached");
+    assert_eq!(requests[0].headers()["authorization"], "Bearer token-1");
+
+    Ok(())
+}
+
+#[test]
+fn it_refreshes_expired_oauth2_token() -> TestResult {
+    let server = oauth_server(0);
+    let quartz = Quartz::preset_using_default_env()?;
+    use_oauth(&quartz, &server)?;
+
+    quartz.cmd(&["send"])?;
+    server.requests();
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    assert!(String::from_utf8(requests[0].body().to_vec())?
+        .starts_with("grant_type=refresh_token&client_id=quartz"),);
+    assert!(String::from_utf8(requests[0].body().to_vec())?.contains("refresh_token=refresh-1"));
+    assert_eq!(requests[1].headers()["authorization"], "Bearer token-2");
+
+    Ok(())
+}
+
+#[test]
+fn it_falls_back_to_grant_when_refresh_is_rejected() -> TestResult {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let issued = AtomicUsize::new(0);
+    let server = Server::new(move |req| {
+        if req.uri().path() != "/token" {
+            return Response::new(Body::empty());
+        }
+
+        if String::from_utf8_lossy(req.body()).starts_with("grant_type=refresh_token") {
+            return Response::builder()
+                .status(400)
+                .body(Body::from(r#"{ "error": "invalid_grant" }"#))
+                .unwrap();
+        }
+
+        let n = issued.fetch_add(1, Ordering::SeqCst) + 1;
+        Response::new(Body::from(format!(
+            r#"{{ "access_token": "token-{n}", "refresh_token": "refresh-{n}", "expires_in": 0 }}"#
+        )))
+    });
+    let quartz = Quartz::preset_using_default_env()?;
+    use_oauth(&quartz, &server)?;
+
+    quartz.cmd(&["send"])?;
+    server.requests();
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 3);
+    assert!(String::from_utf8(requests[0].body().to_vec())?.starts_with("grant_type=refresh_token"));
+    assert!(String::from_utf8(requests[1].body().to_vec())?
+        .starts_with("grant_type=client_credentials"));
+    assert_eq!(requests[2].headers()["authorization"], "Bearer token-2");
+
+    Ok(())
+}
+
+#[test]
+fn it_caches_oauth2_tokens_per_configuration() -> TestResult {
+    let server = oauth_server(3600);
+    let quartz = Quartz::preset_using_default_env()?;
+
+    for (handle, client_id) in [("first", "one"), ("second", "two")] {
+        quartz.cmd(&["create", handle, "--url", &server.url("/api")])?;
+        append_endpoint(
+            &quartz,
+            handle,
+            &format!(
+                "[auth]\ntype = \"oauth2\"\ngrant = \"client_credentials\"\ntoken_url = \"{}\"\nclient_id = \"{client_id}\"\n",
+                server.url("/token")
+            ),
+        )?;
+    }
+
+    quartz.cmd(&["send", "first"])?;
+    quartz.cmd(&["send", "second"])?;
+    server.requests();
+
+    let output = quartz.cmd(&["send", "first"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1, "token was not cached");
+    assert_eq!(requests[0].headers()["authorization"], "Bearer token-1");
+
+    Ok(())
+}
+
+#[test]
+fn it_signs_aws_sigv4_requests() -> TestResult {
+    use chrono::{NaiveDateTime, TimeZone, Utc};
+    use quartz_cli::auth::AwsSigV4;
+
+    let server = Server::ok();
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&[
+        "create",
+        "upload",
+        "-X",
+        "PUT",
+        "--url",
+        &server.url("/bucket/my%20file.txt?versioning&prefix=a%20b"),
+        "--use",
+    ])?;
+    append_endpoint(
+        &quartz,
+        "upload",
+        "[auth]\ntype = \"aws-sigv4\"\nservice = \"s3\"\nregion = \"us-east-1\"\n",
+    )?;
+
+    let output = quartz.cmd_env(
+        &["send", "--data", "hello"],
+        &[
+            ("AWS_ACCESS_KEY_ID", "AKIDEXAMPLE"),
+            ("AWS_SECRET_ACCESS_KEY", "secret"),
+            ("AWS_SESSION_TOKEN", "session"),
+        ],
+    )?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let req = &server.requests()[0];
+    let header = |key: &str| req.headers()[key].to_str().unwrap().to_string();
+
+    let authorization = header("authorization");
+    assert!(
+        authorization.starts_with("AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/"),
+        "{authorization}"
+    );
+    assert_eq!(header("x-amz-security-token"), "session");
+    assert_eq!(
+        header("x-amz-content-sha256"),
+        "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+    );
+
+    // Sign the same request again to compare signatures
+    let signed_headers = authorization
+        .split("SignedHeaders=")
+        .nth(1)
+        .and_then(|rest| rest.split(',').next())
+        .unwrap();
+
+    let mut builder = hyper::Request::builder().method(req.method()).uri(format!(
+        "http://{}{}",
+        header("host"),
+        req.uri()
+    ));
+
+    for key in signed_headers.split(';') {
+        builder = builder.header(key, header(key));
+    }
+
+    let mut expected = builder.body(req.body().to_vec())?;
+    let now = NaiveDateTime::parse_from_str(&header("x-amz-date"), "%Y%m%dT%H%M%SZ")?;
+
+    AwsSigV4 {
+        service: "s3".into(),
+        region: "us-east-1".into(),
+        access_key: "AKIDEXAMPLE".into(),
+        secret_key: "secret".into(),
+        session_token: "session".into(),
+    }
+    .sign(&mut expected, Utc.from_utc_datetime(&now))?;
+
+    assert_eq!(expected.headers()["authorization"], authorization.as_str());
+
+    Ok(())
+}
@@ -0,0 +1,202 @@
+use crate::utils::*;
+
+#[test]
+fn it_sends_given_number_of_requests() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "sample", "--url", &server.url("/"), "--use"])?;
+
+    let output = quartz.cmd(&["bench", "-n", "20", "-c", "4"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert!(
+        output.stdout.contains("Requests:    20"),
+        "{}",
+        output.stdout
+    );
+    assert!(output.stdout.contains("200: 20"), "{}", output.stdout);
+
+    assert_eq!(server.requests().len(), 20);
+
+    Ok(())
+}
+
+#[test]
+fn it_does_not_record_history() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "sample", "--url", &server.url("/")])?;
+
+    let output = quartz.cmd(&["bench", "sample", "-n", "5"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let history = std::fs::read_dir(quartz.dir().join("user/history"))?.count();
+    assert_eq!(history, 0);
+
+    Ok(())
+}
+
+#[test]
+fn it_prints_json_report() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "sample", "--url", &server.url("/"), "--use"])?;
+
+    let output = quartz.cmd(&["bench", "-n", "10", "--json"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let report: serde_json::Value = serde_json::from_str(&output.stdout)?;
+    assert_eq!(report["requests"], 10);
+    assert_eq!(report["status"]["200"], 10);
+    assert!(report["latency"]["p99"].as_f64().is_some());
+
+    Ok(())
+}
+
+#[test]
+fn it_runs_for_given_duration() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "sample", "--url", &server.url("/"), "--use"])?;
+
+    let output = quartz.cmd(&["bench", "--duration", "200ms", "-c", "2"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert!(!server.requests().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn it_fails_when_requests_fail() -> TestResult {
+    let quartz = Quartz::preset_empty_project()?;
+    let addr = std::net::TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+
+    let url = format!("http://{addr}/");
+    quartz.cmd(&["create", "sample", "--url", &url, "--use"])?;
+
+    let output = quartz.cmd(&["bench", "-n", "3"])?;
+    assert!(!output.status.success(), "{}", output.stdout);
+    assert!(output.stdout.contains("Error:"), "{}", output.stdout);
+
+    Ok(())
+}
+
+#[test]
+fn it_renders_built_in_variables_for_each_request() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&[
+        "create",
+        "sample",
+        "--url",
+        &server.url("/"),
+        "-H",
+        "X-Request-Id: {{$uuid}}",
+        "--use",
+    ])?;
+
+    let output = quartz.cmd(&["bench", "-n", "5"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let mut ids: Vec<String> = server
+        .requests()
+        .iter()
+        .map(|req| req.headers()["x-request-id"].to_str().unwrap().to_string())
+        .collect();
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), 5, "{ids:?}");
+
+    Ok(())
+}
+
+#[test]
+fn it_overlays_variables_from_env_files() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_using_default_env()?;
+
+    quartz.cmd(&[
+        "create",
+        "users",
+        "--url",
+        &server.url("/{{version}}"),
+        "--use",
+    ])?;
+    quartz.cmd(&["var", "set", "version=v1"])?;
+
+    let file = quartz.dir().join("overlay.env");
+    std::fs::write(&file, "version=v2\n")?;
+
+    let output = quartz.cmd(&["bench", "-n", "3", "--env-file", file.to_str().unwrap()])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let paths: Vec<String> = server
+        .requests()
+        .iter()
+        .map(|req| req.uri().path().to_string())
+        .collect();
+    assert_eq!(paths, ["/v2", "/v2", "/v2"]);
+
+    Ok(())
+}
+
+#[test]
+fn it_authenticates_every_request() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "api", "--url", &server.url("/")])?;
+    quartz.cmd(&["create", "api/users", "--url", "**/users", "--use"])?;
+
+    let path = quartz.dir().join("endpoints/api/endpoint.toml");
+    let content = std::fs::read_to_string(&path)?;
+    std::fs::write(
+        &path,
+        format!("{content}\n[auth]\ntype = \"bearer\"\ntoken = \"abc\"\n"),
+    )?;
+
+    let output = quartz.cmd(&["bench", "-n", "3"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 3);
+    for req in requests {
+        assert_eq!(req.uri().path(), "/users");
+        assert_eq!(req.headers()["authorization"], "Bearer abc");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn it_sends_scoped_headers_and_body() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "api", "--url", &server.url("/")])?;
+    quartz.cmd(&["create", "api/users", "--url", "**/users", "--use"])?;
+    quartz.cmd(&["header", "set", "--scope", "api", "X-Tenant: acme"])?;
+    quartz.cmd_stdin(&["body", "stdin"], r#"{ "id": "{{$uuid}}" }"#)?;
+
+    let output = quartz.cmd(&["bench", "-n", "3"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 3);
+
+    let mut bodies: Vec<_> = requests.iter().map(|req| req.body().clone()).collect();
+    bodies.sort();
+    bodies.dedup();
+    assert_eq!(bodies.len(), 3, "{bodies:?}");
+
+    for req in requests {
+        assert_eq!(req.uri().path(), "/users");
+        assert_eq!(req.headers()["x-tenant"], "acme");
+    }
+
+    Ok(())
+}
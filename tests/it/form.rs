@@ -0,0 +1,193 @@
+use crate::utils::*;
+
+#[test]
+fn it_sends_multipart_form() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_empty_project()?;
+
+    std::fs::write(
+        quartz.dir().parent().unwrap().join("users.csv"),
+        "id,name\n1,Jane\n",
+    )?;
+
+    quartz.cmd(&[
+        "create",
+        "upload",
+        "-X",
+        "POST",
+        "--url",
+        &server.url("/"),
+        "--use",
+    ])?;
+
+    let output = quartz.cmd(&["send", "-F", "kind=users", "-F", "file=@users.csv"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let req = &server.requests()[0];
+    let content_type = req.headers()["content-type"].to_str()?;
+    let boundary = content_type
+        .strip_prefix("multipart/form-data; boundary=")
+        .expect(content_type);
+
+    let body = String::from_utf8(req.body().to_vec())?;
+    assert_eq!(
+        body,
+        format!(
+            "--{boundary}\r\n\
+            Content-Disposition: form-data; name=\"kind\"\r\n\r\n\
+            users\r\n\
+            --{boundary}\r\n\
+            Content-Disposition: form-data; name=\"file\"; filename=\"users.csv\"\r\n\
+            Content-Type: text/csv\r\n\r\n\
+            id,name\n1,Jane\n\r\n\
+            --{boundary}--\r\n"
+        )
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_records_the_multipart_boundary_that_was_sent() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&[
+        "create",
+        "upload",
+        "-X",
+        "POST",
+        "--url",
+        &server.url("/"),
+        "--use",
+    ])?;
+
+    let output = quartz.cmd(&["send", "-F", "kind=users"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let req = &server.requests()[0];
+    let content_type = req.headers()["content-type"].to_str()?;
+
+    let last = quartz.cmd(&["last"])?;
+    assert!(last.stdout.contains(content_type), "{}", last.stdout);
+
+    Ok(())
+}
+
+#[test]
+fn it_persists_form_fields() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&[
+        "create",
+        "upload",
+        "-X",
+        "POST",
+        "--url",
+        &server.url("/"),
+        "--use",
+    ])?;
+
+    let output = quartz.cmd(&["use", "-F", "name=Jane", "-F", "age=30"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let output = quartz.cmd(&["use", "--form", "age=31"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let form = std::fs::read_to_string(quartz.dir().join("endpoints/upload/form"))?;
+    assert_eq!(form, "name=Jane\nage=31\n");
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let body = String::from_utf8(server.requests()[0].body().to_vec())?;
+    assert!(body.contains("name=\"age\"\r\n\r\n31\r\n"), "{body}");
+
+    Ok(())
+}
+
+#[test]
+fn it_fails_on_missing_form_file() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&[
+        "create",
+        "upload",
+        "-X",
+        "POST",
+        "--url",
+        &server.url("/"),
+        "--use",
+    ])?;
+
+    let output = quartz.cmd(&["send", "-F", "file=@missing.png"])?;
+    assert!(!output.status.success());
+    assert!(output.stderr.contains("missing.png"), "{}", output.stderr);
+
+    Ok(())
+}
+
+#[test]
+fn it_sends_urlencoded_form() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&[
+        "create",
+        "token",
+        "-X",
+        "POST",
+        "--url",
+        &server.url("/"),
+        "--use",
+    ])?;
+    quartz.cmd(&["var", "set", "secret=a&b c"])?;
+
+    let output = quartz.cmd(&[
+        "use",
+        "--form-urlencoded",
+        "grant_type=client_credentials",
+        "--form-urlencoded",
+        "client_secret={{secret}}",
+    ])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let form = std::fs::read_to_string(quartz.dir().join("endpoints/token/form-urlencoded"))?;
+    assert_eq!(
+        form,
+        "grant_type=client_credentials\nclient_secret={{secret}}\n"
+    );
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let req = &server.requests()[0];
+    assert_eq!(
+        req.headers()["content-type"],
+        "application/x-www-form-urlencoded"
+    );
+    assert_eq!(
+        req.body(),
+        "grant_type=client_credentials&client_secret=a%26b+c"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_replaces_form_with_other_encoding() -> TestResult {
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "token", "-X", "POST", "--use", "-F", "a=1"])?;
+    assert!(quartz.dir().join("endpoints/token/form").exists());
+
+    quartz.cmd(&["use", "--form-urlencoded", "b=2"])?;
+    assert!(!quartz.dir().join("endpoints/token/form").exists());
+
+    let form = std::fs::read_to_string(quartz.dir().join("endpoints/token/form-urlencoded"))?;
+    assert_eq!(form, "b=2\n");
+
+    Ok(())
+}
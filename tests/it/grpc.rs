@@ -0,0 +1,319 @@
+use hyper::body::Bytes;
+use hyper::header::HeaderValue;
+use hyper::{Body, HeaderMap, Request, Response};
+
+use crate::utils::*;
+
+const ECHO_PROTO: &str = r#"
+syntax = "proto3";
+
+package echo;
+
+message EchoMessage {
+    string text = 1;
+    int32 count = 2;
+}
+
+service Echo {
+    rpc Echo(EchoMessage) returns (EchoMessage);
+}
+"#;
+
+/// Replies every request with its own body, followed by a successful `grpc-status` trailer.
+fn echo(req: &Request<Bytes>) -> Response<Body> {
+    let bytes = req.body().clone();
+    let (mut sender, body) = Body::channel();
+
+    tokio::spawn(async move {
+        let _ = sender.send_data(bytes).await;
+
+        let mut trailers = HeaderMap::new();
+        trailers.insert("grpc-status", HeaderValue::from_static("0"));
+        let _ = sender.send_trailers(trailers).await;
+    });
+
+    Response::builder()
+        .header("content-type", "application/grpc")
+        .body(body)
+        .unwrap()
+}
+
+#[test]
+fn it_creates_grpc_endpoint() -> TestResult {
+    let quartz = Quartz::preset_empty_project()?;
+
+    let output = quartz.cmd(&[
+        "create",
+        "echo",
+        "--url",
+        "localhost:50051",
+        "--proto",
+        "echo.proto",
+        "--rpc",
+        "echo.Echo/Echo",
+    ])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let output = quartz.cmd(&["-x", "echo", "show", "endpoint"])?;
+    assert!(output.stdout.contains("[grpc]"), "{}", output.stdout);
+    assert!(
+        output.stdout.contains("service = \"echo.Echo\""),
+        "{}",
+        output.stdout
+    );
+    assert!(
+        output.stdout.contains("method = \"Echo\""),
+        "{}",
+        output.stdout
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_requires_proto_file() -> TestResult {
+    let quartz = Quartz::preset_empty_project()?;
+
+    let output = quartz.cmd(&["create", "echo", "--rpc", "echo.Echo/Echo"])?;
+    assert!(!output.status.success());
+
+    Ok(())
+}
+
+#[test]
+fn it_sends_grpc_request() -> TestResult {
+    let server = Server::new(echo);
+
+    let quartz = Quartz::preset_empty_project()?;
+    std::fs::write(
+        quartz.dir().parent().unwrap().join("echo.proto"),
+        ECHO_PROTO,
+    )?;
+
+    quartz.cmd(&[
+        "create",
+        "echo",
+        "--url",
+        &server.addr().to_string(),
+        "--proto",
+        "echo.proto",
+        "--rpc",
+        "echo.Echo/Echo",
+        "--use",
+    ])?;
+    quartz.cmd_stdin(&["body", "stdin"], r#"{ "text": "hello", "count": 3 }"#)?;
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert!(
+        output.stdout.contains(r#""text": "hello""#),
+        "{}",
+        output.stdout
+    );
+    assert!(output.stdout.contains(r#""count": 3"#), "{}", output.stdout);
+
+    Ok(())
+}
+
+#[test]
+fn it_sends_grpc_request_through_proxy() -> TestResult {
+    let server = Server::new(echo);
+    let proxy = TunnelProxy::new(server.addr());
+
+    let quartz = Quartz::preset_empty_project()?;
+    std::fs::write(
+        quartz.dir().parent().unwrap().join("echo.proto"),
+        ECHO_PROTO,
+    )?;
+
+    quartz.cmd(&[
+        "create",
+        "echo",
+        "--url",
+        "http://quartz.invalid",
+        "--proto",
+        "echo.proto",
+        "--rpc",
+        "echo.Echo/Echo",
+        "--use",
+    ])?;
+    quartz.cmd_stdin(&["body", "stdin"], r#"{ "text": "hello" }"#)?;
+
+    let output = quartz.cmd(&["send", "--proxy", &format!("user:secret@{}", proxy.addr())])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert!(
+        output.stdout.contains(r#""text": "hello""#),
+        "{}",
+        output.stdout
+    );
+
+    let requests = proxy.requests();
+    assert!(
+        requests[0].starts_with("CONNECT quartz.invalid:80 HTTP/1.1\r\n"),
+        "{}",
+        requests[0]
+    );
+    assert!(
+        requests[0].contains("Proxy-Authorization: Basic dXNlcjpzZWNyZXQ=\r\n"),
+        "{}",
+        requests[0]
+    );
+
+    // Proxy credentials are not forwarded through the tunnel
+    let requests = server.requests();
+    assert_eq!(requests[0].uri().path(), "/echo.Echo/Echo");
+    assert!(!requests[0].headers().contains_key("proxy-authorization"));
+
+    Ok(())
+}
+
+#[test]
+fn it_applies_tls_options_to_grpc_requests() -> TestResult {
+    let server = Server::https(echo);
+
+    let quartz = Quartz::preset_empty_project()?;
+    let dir = quartz.dir().parent().unwrap().to_path_buf();
+    std::fs::write(dir.join("echo.proto"), ECHO_PROTO)?;
+    std::fs::write(dir.join("ca.pem"), server.certificate())?;
+
+    quartz.cmd(&[
+        "create",
+        "echo",
+        "--url",
+        &server.url("/"),
+        "--proto",
+        "echo.proto",
+        "--rpc",
+        "echo.Echo/Echo",
+        "--use",
+    ])?;
+    quartz.cmd_stdin(&["body", "stdin"], r#"{ "text": "hello" }"#)?;
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(!output.status.success(), "{}", output.stdout);
+
+    let output = quartz.cmd(&["send", "--cacert", "ca.pem"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert!(
+        output.stdout.contains(r#""text": "hello""#),
+        "{}",
+        output.stdout
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_times_out_slow_grpc_responses() -> TestResult {
+    let server = Server::new(|_| {
+        // Body never finishes while its sender is alive
+        let (sender, body) = Body::channel();
+        std::mem::forget(sender);
+
+        Response::builder()
+            .header("content-type", "application/grpc")
+            .body(body)
+            .unwrap()
+    });
+
+    let quartz = Quartz::preset_empty_project()?;
+    std::fs::write(
+        quartz.dir().parent().unwrap().join("echo.proto"),
+        ECHO_PROTO,
+    )?;
+
+    quartz.cmd(&[
+        "create",
+        "echo",
+        "--url",
+        &server.addr().to_string(),
+        "--proto",
+        "echo.proto",
+        "--rpc",
+        "echo.Echo/Echo",
+        "--use",
+    ])?;
+    quartz.cmd_stdin(&["body", "stdin"], r#"{ "text": "hello" }"#)?;
+
+    let output = quartz.cmd(&["send", "--read-timeout", "0.2"])?;
+    assert!(!output.status.success());
+    assert!(output.stderr.contains("timed out"), "{}", output.stderr);
+
+    let output = quartz.cmd(&["send", "--max-time", "0.2"])?;
+    assert!(!output.status.success());
+    assert!(output.stderr.contains("timed out"), "{}", output.stderr);
+
+    Ok(())
+}
+
+#[test]
+fn it_authenticates_grpc_requests() -> TestResult {
+    let server = Server::new(echo);
+
+    let quartz = Quartz::preset_empty_project()?;
+    std::fs::write(
+        quartz.dir().parent().unwrap().join("echo.proto"),
+        ECHO_PROTO,
+    )?;
+
+    quartz.cmd(&[
+        "create",
+        "echo",
+        "--url",
+        &server.addr().to_string(),
+        "--proto",
+        "echo.proto",
+        "--rpc",
+        "echo.Echo/Echo",
+        "--use",
+    ])?;
+    quartz.cmd_stdin(&["body", "stdin"], r#"{ "text": "hello" }"#)?;
+
+    let path = quartz.dir().join("endpoints/echo/endpoint.toml");
+    let current = std::fs::read_to_string(&path)?;
+    std::fs::write(
+        &path,
+        format!("{current}\n[auth]\ntype = \"bearer\"\ntoken = \"abc\"\n"),
+    )?;
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(
+        server.requests()[0].headers()["authorization"],
+        "Bearer abc"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_resolves_grpc_hosts_to_given_address() -> TestResult {
+    let server = Server::new(echo);
+    let port = server.addr().port();
+
+    let quartz = Quartz::preset_empty_project()?;
+    std::fs::write(
+        quartz.dir().parent().unwrap().join("echo.proto"),
+        ECHO_PROTO,
+    )?;
+
+    quartz.cmd(&[
+        "create",
+        "echo",
+        "--url",
+        &format!("http://api.quartz.test:{port}"),
+        "--proto",
+        "echo.proto",
+        "--rpc",
+        "echo.Echo/Echo",
+        "--use",
+    ])?;
+    quartz.cmd_stdin(&["body", "stdin"], r#"{ "text": "hello" }"#)?;
+
+    let resolve = format!("api.quartz.test:{port}:127.0.0.1");
+    let output = quartz.cmd(&["send", "--resolve", &resolve])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(server.requests().len(), 1);
+
+    Ok(())
+}
@@ -139,3 +139,47 @@ fn compatible_with_apply_env_option() -> TestResult {
 
     Ok(())
 }
+
+#[test]
+fn it_inherits_scoped_headers() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_using_default_env()?;
+
+    quartz.cmd(&[
+        "create",
+        "billing/invoices",
+        "--url",
+        &server.url("/invoices"),
+    ])?;
+    quartz.cmd(&[
+        "create",
+        "billing/customers",
+        "--url",
+        &server.url("/customers"),
+        "-H",
+        "X-Tenant: own",
+    ])?;
+
+    // "billing" has no endpoint of its own
+    let output = quartz.cmd(&["header", "set", "--scope", "billing", "X-Tenant: acme"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let output = quartz.cmd(&["header", "ls", "--scope", "billing"])?;
+    assert_eq!(output.stdout.trim(), "X-Tenant: acme");
+
+    for handle in ["billing/invoices", "billing/customers"] {
+        let output = quartz.cmd(&["send", handle])?;
+        assert!(output.status.success(), "{}", output.stderr);
+    }
+
+    let requests = server.requests();
+    assert_eq!(requests[0].headers()["x-tenant"], "acme");
+    assert_eq!(requests[1].headers()["x-tenant"], "own");
+
+    // Only sent, not copied to endpoints
+    quartz.cmd(&["use", "billing/invoices"])?;
+    let output = quartz.cmd(&["header", "ls"])?;
+    assert!(!output.stdout.contains("acme"), "{}", output.stdout);
+
+    Ok(())
+}
@@ -1,9 +1,18 @@
+pub mod auth;
+pub mod bench;
 pub mod body;
 pub mod config;
 pub mod endpoint;
 pub mod env;
+pub mod form;
+pub mod grpc;
 pub mod header;
 pub mod init;
 pub mod op;
 pub mod query;
+pub mod redact;
+pub mod redirect;
+pub mod secret;
+pub mod send;
+pub mod tls;
 pub mod var;
@@ -0,0 +1,122 @@
+use hyper::{Body, Response};
+
+use crate::utils::*;
+
+#[test]
+fn it_redacts_sensitive_headers_from_history() -> TestResult {
+    let server = Server::new(|_| {
+        Response::builder()
+            .header("Set-Cookie", "session=abc123")
+            .header("X-Request-Id", "42")
+            .body(Body::from("ok"))
+            .unwrap()
+    });
+    let quartz = Quartz::preset_using_default_env()?;
+
+    quartz.cmd(&[
+        "create",
+        "private",
+        "--url",
+        &server.url("/private"),
+        "-H",
+        "Authorization: Bearer t0k3n",
+        "-H",
+        "X-Api-Key: k3y",
+        "-H",
+        "Accept: text/plain",
+        "--use",
+    ])?;
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let requests = server.requests();
+    assert_eq!(requests[0].headers()["authorization"], "Bearer t0k3n");
+
+    let last = quartz.cmd(&["last"])?;
+    for value in ["t0k3n", "k3y", "abc123"] {
+        assert!(!last.stdout.contains(value), "{}", last.stdout);
+    }
+    assert!(
+        last.stdout.contains("accept: text/plain"),
+        "{}",
+        last.stdout
+    );
+    assert!(last.stdout.contains("x-request-id: 42"), "{}", last.stdout);
+    assert!(last.stdout.contains("set-cookie: ****"), "{}", last.stdout);
+
+    Ok(())
+}
+
+#[test]
+fn it_redacts_sensitive_headers_from_snippets() -> TestResult {
+    let quartz = Quartz::preset_using_default_env()?;
+
+    quartz.cmd(&[
+        "create",
+        "private",
+        "--url",
+        "http://localhost/private",
+        "-H",
+        "Cookie: session=abc123",
+        "-H",
+        "Accept: text/plain",
+        "--use",
+    ])?;
+
+    for format in ["curl", "http"] {
+        let output = quartz.cmd(&["show", "snippet", format])?;
+        assert!(output.status.success(), "{}", output.stderr);
+        assert!(!output.stdout.contains("abc123"), "{}", output.stdout);
+        assert!(output.stdout.contains("Cookie: ****"), "{}", output.stdout);
+        assert!(
+            output.stdout.contains("Accept: text/plain"),
+            "{}",
+            output.stdout
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn it_can_configure_redacted_headers() -> TestResult {
+    let quartz = Quartz::preset_using_default_env()?;
+    let dir = quartz.dir();
+    let home = [("HOME", dir.to_str().unwrap())];
+
+    quartz.cmd(&[
+        "create",
+        "private",
+        "--url",
+        "http://localhost/private",
+        "-H",
+        "Cookie: session=abc123",
+        "-H",
+        "X-Custom: hidden",
+        "--use",
+    ])?;
+
+    let output = quartz.cmd_env(
+        &["config", "set", "preferences.redact", "X-Custom, X-Other"],
+        &home,
+    )?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let output = quartz.cmd_env(&["config", "get", "preferences.redact"], &home)?;
+    assert_eq!(output.stdout.trim(), "X-Custom,X-Other");
+
+    let output = quartz.cmd_env(&["show", "snippet", "http"], &home)?;
+    assert!(
+        output.stdout.contains("X-Custom: ****"),
+        "{}",
+        output.stdout
+    );
+    assert!(
+        output.stdout.contains("Cookie: session=abc123"),
+        "{}",
+        output.stdout
+    );
+
+    Ok(())
+}
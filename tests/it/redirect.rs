@@ -0,0 +1,179 @@
+use hyper::{Body, Method, Response};
+
+use crate::utils::*;
+
+fn redirect(status: u16, location: &str) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header("Location", location)
+        .body(Body::empty())
+        .unwrap()
+}
+
+#[test]
+fn it_follows_relative_locations() -> TestResult {
+    let server = Server::new(|req| match req.uri().path() {
+        "/v1/users/me" => redirect(302, "profile?tab=1"),
+        "/v1/users/profile" => redirect(301, "../teams"),
+        _ => Response::new(Body::from(req.uri().to_string())),
+    });
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&[
+        "create",
+        "sample",
+        "--url",
+        &server.url("/v1/users/me"),
+        "--use",
+    ])?;
+    quartz.cmd(&["query", "set", "page=2"])?;
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(output.stdout, "/v1/teams");
+
+    let uris: Vec<String> = server
+        .requests()
+        .iter()
+        .map(|r| r.uri().to_string())
+        .collect();
+    assert_eq!(
+        uris,
+        [
+            "/v1/users/me?page=2",
+            "/v1/users/profile?tab=1",
+            "/v1/teams"
+        ]
+    );
+
+    // Every hop is recorded
+    let output = quartz.cmd(&["last", "req"])?;
+    assert!(
+        output.stdout.contains("/v1/users/profile?tab=1"),
+        "{}",
+        output.stdout
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_stops_at_max_redirects() -> TestResult {
+    let server = Server::new(|_| redirect(302, "/loop"));
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "sample", "--url", &server.url("/loop"), "--use"])?;
+
+    let output = quartz.cmd(&["send", "--max-redirects", "3"])?;
+    assert!(!output.status.success());
+    assert!(output.stderr.contains("redirects"), "{}", output.stderr);
+    assert_eq!(server.requests().len(), 4);
+
+    Ok(())
+}
+
+#[test]
+fn it_switches_to_get_after_see_other() -> TestResult {
+    let server = Server::new(|req| match req.uri().path() {
+        "/submit" => redirect(303, "/done"),
+        _ => Response::new(Body::empty()),
+    });
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&[
+        "create",
+        "sample",
+        "-X",
+        "POST",
+        "--url",
+        &server.url("/submit"),
+        "--use",
+    ])?;
+
+    let output = quartz.cmd(&["send", "--json", "{\"a\": 1}"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let requests = server.requests();
+    assert_eq!(requests[0].method(), Method::POST);
+    assert_eq!(requests[1].method(), Method::GET);
+    assert!(requests[1].body().is_empty());
+    assert!(!requests[1].headers().contains_key("content-type"));
+
+    Ok(())
+}
+
+#[test]
+fn it_keeps_method_after_temporary_redirect() -> TestResult {
+    let server = Server::new(|req| match req.uri().path() {
+        "/submit" => redirect(307, "/done"),
+        _ => Response::new(Body::empty()),
+    });
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&[
+        "create",
+        "sample",
+        "-X",
+        "POST",
+        "--url",
+        &server.url("/submit"),
+        "--use",
+    ])?;
+
+    let output = quartz.cmd(&["send", "--data", "hello"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let requests = server.requests();
+    assert_eq!(requests[1].method(), Method::POST);
+    assert_eq!(requests[1].body(), "hello");
+
+    Ok(())
+}
+
+#[test]
+fn it_drops_credentials_across_origins() -> TestResult {
+    let other = Server::ok();
+    let location = other.url("/landing");
+    let server = Server::new(move |_| redirect(302, &location));
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "sample", "--url", &server.url("/"), "--use"])?;
+
+    let output = quartz.cmd(&[
+        "send",
+        "-H",
+        "Authorization: Bearer secret",
+        "--cookie",
+        "session=abc",
+    ])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let first = &server.requests()[0];
+    assert!(first.headers().contains_key("authorization"));
+    assert!(first.headers().contains_key("cookie"));
+
+    let second = &other.requests()[0];
+    assert!(!second.headers().contains_key("authorization"));
+    assert!(!second.headers().contains_key("cookie"));
+
+    Ok(())
+}
+
+#[test]
+fn it_fails_on_invalid_location() -> TestResult {
+    let server = Server::new(|_| redirect(302, "http://exa mple.com/"));
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "sample", "--url", &server.url("/"), "--use"])?;
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(!output.status.success());
+    assert!(
+        output.stderr.contains("invalid redirect location"),
+        "{}",
+        output.stderr
+    );
+    assert_eq!(server.requests().len(), 1);
+
+    Ok(())
+}
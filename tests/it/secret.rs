@@ -0,0 +1,176 @@
+use hyper::{Body, Response};
+
+use crate::utils::*;
+
+const PASSPHRASE: (&str, &str) = ("QUARTZ_SECRET_PASSPHRASE", "correct horse");
+
+#[test]
+fn it_can_set_and_get_secrets() -> TestResult {
+    let quartz = Quartz::preset_using_default_env()?;
+
+    let output = quartz.cmd_env(&["secret", "set", "token=s3cr3t"], &[PASSPHRASE])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let output = quartz.cmd_env(&["secret", "get", "token"], &[PASSPHRASE])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(output.stdout.trim(), "s3cr3t");
+
+    let output = quartz.cmd(&["secret", "ls"])?;
+    assert_eq!(output.stdout.trim(), "token");
+
+    let stored = std::fs::read_to_string(quartz.dir().join("env/default/secrets.enc"))?;
+    assert!(!stored.contains("s3cr3t"), "{stored}");
+
+    let output = quartz.cmd_env(&["secret", "rm", "token"], &[PASSPHRASE])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert!(!quartz.dir().join("env/default/secrets.enc").exists());
+
+    Ok(())
+}
+
+#[test]
+fn it_reads_secret_values_from_stdin() -> TestResult {
+    let quartz = Quartz::preset_using_default_env()?;
+
+    let key_file = quartz.dir().join("key");
+    std::fs::write(&key_file, "from a file\n")?;
+    let key_file = ("QUARTZ_SECRET_KEY_FILE", key_file.to_str().unwrap());
+
+    let mut command = std::process::Command::new(env!("CARGO_BIN_EXE_quartz"));
+    command
+        .args(["secret", "set", "token"])
+        .current_dir(quartz.dir().parent().unwrap())
+        .env(key_file.0, key_file.1)
+        .stdin(std::process::Stdio::piped());
+
+    let mut child = command.spawn()?;
+    std::io::Write::write_all(child.stdin.as_mut().unwrap(), b"piped\n")?;
+    assert!(child.wait()?.success());
+
+    let output = quartz.cmd_env(&["secret", "get", "token"], &[key_file])?;
+    assert_eq!(output.stdout.trim(), "piped");
+
+    Ok(())
+}
+
+#[test]
+fn it_fails_with_wrong_passphrase() -> TestResult {
+    let quartz = Quartz::preset_using_default_env()?;
+
+    quartz.cmd_env(&["secret", "set", "token=s3cr3t"], &[PASSPHRASE])?;
+
+    let output = quartz.cmd_env(
+        &["secret", "get", "token"],
+        &[("QUARTZ_SECRET_PASSPHRASE", "wrong")],
+    )?;
+    assert!(!output.status.success());
+    assert!(
+        output.stderr.contains("wrong passphrase"),
+        "{}",
+        output.stderr
+    );
+
+    let output = quartz.cmd_env(
+        &["secret", "set", "other=value"],
+        &[("QUARTZ_SECRET_PASSPHRASE", "wrong")],
+    )?;
+    assert!(!output.status.success());
+
+    Ok(())
+}
+
+#[test]
+fn it_sends_secrets_and_masks_them() -> TestResult {
+    let server = Server::new(|req| {
+        let auth = req
+            .headers()
+            .get("authorization")
+            .unwrap()
+            .to_str()
+            .unwrap();
+        Response::new(Body::from(auth.to_string()))
+    });
+    let quartz = Quartz::preset_using_default_env()?;
+
+    quartz.cmd(&[
+        "create",
+        "private",
+        "--url",
+        &server.url("/private"),
+        "-H",
+        "Authorization: Bearer {{token}}",
+        "--use",
+    ])?;
+    quartz.cmd(&["var", "set", "user=admin"])?;
+    quartz.cmd_env(&["secret", "set", "token=s3cr3t"], &[PASSPHRASE])?;
+
+    let output = quartz.cmd_env(&["send"], &[PASSPHRASE])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(output.stdout.trim(), "Bearer s3cr3t");
+
+    let last = quartz.cmd(&["last"])?;
+    assert!(!last.stdout.contains("s3cr3t"), "{}", last.stdout);
+    assert!(last.stdout.contains("****"), "{}", last.stdout);
+
+    let ls = quartz.cmd(&["var", "ls"])?;
+    assert!(ls.stdout.contains("user=admin"), "{}", ls.stdout);
+    assert!(ls.stdout.contains("token=****"), "{}", ls.stdout);
+
+    let snippet = quartz.cmd(&["show", "snippet", "http"])?;
+    assert!(!snippet.stdout.contains("s3cr3t"), "{}", snippet.stdout);
+    assert!(snippet.stdout.contains("****"), "{}", snippet.stdout);
+
+    Ok(())
+}
+
+#[test]
+fn it_inherits_secrets_from_extended_env() -> TestResult {
+    let server = Server::new(|req| {
+        let auth = req.headers()["x-token"].to_str().unwrap();
+        Response::new(Body::from(auth.to_string()))
+    });
+    let quartz = Quartz::preset_using_default_env()?;
+
+    quartz.cmd(&[
+        "create",
+        "private",
+        "--url",
+        &server.url("/private"),
+        "-H",
+        "X-Token: {{token}}",
+        "--use",
+    ])?;
+    quartz.cmd_env(&["secret", "set", "token=s3cr3t"], &[PASSPHRASE])?;
+    quartz.cmd(&["env", "create", "dev", "--extends", "default"])?;
+    quartz.cmd(&["env", "use", "dev"])?;
+
+    let output = quartz.cmd_env(&["send"], &[PASSPHRASE])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(output.stdout.trim(), "s3cr3t");
+
+    let ls = quartz.cmd(&["var", "ls"])?;
+    assert!(ls.stdout.contains("token=****"), "{}", ls.stdout);
+
+    Ok(())
+}
+
+#[test]
+fn secrets_override_scoped_variables() -> TestResult {
+    let server = Server::new(|req| Response::new(Body::from(req.uri().path().to_string())));
+    let quartz = Quartz::preset_using_default_env()?;
+
+    quartz.cmd(&[
+        "create",
+        "billing/invoices",
+        "--url",
+        &server.url("/{{token}}"),
+    ])?;
+    quartz.cmd(&["var", "set", "--scope", "billing", "token=scoped"])?;
+    quartz.cmd_env(&["secret", "set", "token=s3cr3t"], &[PASSPHRASE])?;
+
+    let output = quartz.cmd_env(&["send", "billing/invoices"], &[PASSPHRASE])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(output.stdout.trim(), "/s3cr3t");
+
+    Ok(())
+}
@@ -0,0 +1,713 @@
+use hyper::{Body, Response, StatusCode, Version};
+
+use crate::utils::*;
+
+#[test]
+fn it_sends_http1_by_default() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "sample", "--url", &server.url("/"), "--use"])?;
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(server.requests()[0].version(), Version::HTTP_11);
+
+    Ok(())
+}
+
+#[test]
+fn it_sends_http2_with_prior_knowledge() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "sample", "--url", &server.url("/"), "--use"])?;
+
+    let output = quartz.cmd(&["send", "--http2-prior-knowledge"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(server.requests()[0].version(), Version::HTTP_2);
+
+    let output = quartz.cmd(&["last", "res", "head"])?;
+    assert!(output.stdout.contains("HTTP/2.0 200"), "{}", output.stdout);
+
+    Ok(())
+}
+
+/// HTTP/2 negotiation happens over TLS, so plain HTTP keeps HTTP/1.1.
+#[test]
+fn it_falls_back_to_http1_without_tls() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "sample", "--url", &server.url("/"), "--use"])?;
+
+    let output = quartz.cmd(&["send", "--http2"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(server.requests()[0].version(), Version::HTTP_11);
+
+    Ok(())
+}
+
+#[test]
+fn it_uses_endpoint_version() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "sample", "--url", &server.url("/"), "--use"])?;
+
+    let path = quartz.dir().join("endpoints/sample/endpoint.toml");
+    let content = std::fs::read_to_string(&path)?;
+    std::fs::write(&path, format!("version = \"2-prior-knowledge\"\n{content}"))?;
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(server.requests()[0].version(), Version::HTTP_2);
+
+    let output = quartz.cmd(&["send", "--http1.1"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(server.requests()[0].version(), Version::HTTP_11);
+
+    Ok(())
+}
+
+#[test]
+fn it_sends_through_proxy() -> TestResult {
+    let proxy = Server::ok();
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&[
+        "create",
+        "sample",
+        "--url",
+        "http://quartz.invalid/hello",
+        "--use",
+    ])?;
+
+    let output = quartz.cmd(&["send", "--proxy", &format!("user:secret@{}", proxy.addr())])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let requests = proxy.requests();
+    assert_eq!(requests[0].uri(), "http://quartz.invalid/hello");
+    assert_eq!(
+        requests[0].headers()["proxy-authorization"],
+        "Basic dXNlcjpzZWNyZXQ="
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_uses_proxy_environment_variables() -> TestResult {
+    let proxy = Server::ok();
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&[
+        "create",
+        "sample",
+        "--url",
+        "http://quartz.invalid/hello",
+        "--use",
+    ])?;
+
+    let output = quartz.cmd_env(&["send"], &[("HTTP_PROXY", &proxy.url("/"))])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(proxy.requests()[0].uri(), "http://quartz.invalid/hello");
+
+    Ok(())
+}
+
+#[test]
+fn it_skips_proxy_for_no_proxy_hosts() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "sample", "--url", &server.url("/"), "--use"])?;
+
+    let output = quartz.cmd_env(
+        &["send"],
+        &[
+            ("HTTP_PROXY", "http://127.0.0.1:1"),
+            ("NO_PROXY", "localhost,127.0.0.1"),
+        ],
+    )?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(server.requests().len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn it_tunnels_https_through_proxy() -> TestResult {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let addr = listener.local_addr()?;
+
+    let proxy = std::thread::spawn(move || {
+        use std::io::{Read, Write};
+
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut req = Vec::new();
+        let mut buf = [0; 1024];
+
+        while !req.windows(4).any(|w| w == b"\r\n\r\n") {
+            let n = stream.read(&mut buf).unwrap();
+            req.extend_from_slice(&buf[..n]);
+        }
+
+        stream
+            .write_all(b"HTTP/1.1 407 Proxy Authentication Required\r\n\r\n")
+            .unwrap();
+
+        String::from_utf8(req).unwrap()
+    });
+
+    let quartz = Quartz::preset_empty_project()?;
+    quartz.cmd(&[
+        "create",
+        "sample",
+        "--url",
+        "https://quartz.invalid/",
+        "--use",
+    ])?;
+
+    let output = quartz.cmd(&["send", "--proxy", &format!("user:secret@{addr}")])?;
+    assert!(!output.status.success());
+    assert!(output.stderr.contains("407"), "{}", output.stderr);
+
+    let req = proxy.join().unwrap();
+    assert!(
+        req.starts_with("CONNECT quartz.invalid:443 HTTP/1.1\r\n"),
+        "{req}"
+    );
+    assert!(
+        req.contains("Proxy-Authorization: Basic dXNlcjpzZWNyZXQ=\r\n"),
+        "{req}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_tunnels_http2_prior_knowledge_through_proxy() -> TestResult {
+    let server = Server::ok();
+    let proxy = TunnelProxy::new(server.addr());
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&[
+        "create",
+        "sample",
+        "--url",
+        "http://quartz.invalid/",
+        "--use",
+    ])?;
+
+    let output = quartz.cmd(&[
+        "send",
+        "--http2-prior-knowledge",
+        "--proxy",
+        &proxy.addr().to_string(),
+    ])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(server.requests()[0].version(), Version::HTTP_2);
+
+    let requests = proxy.requests();
+    assert!(
+        requests[0].starts_with("CONNECT quartz.invalid:80 HTTP/1.1\r\n"),
+        "{}",
+        requests[0]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_retries_failed_requests() -> TestResult {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let attempts = AtomicUsize::new(0);
+    let server = Server::new(move |_| {
+        let status = match attempts.fetch_add(1, Ordering::SeqCst) {
+            0 => 503,
+            _ => 200,
+        };
+
+        hyper::Response::builder()
+            .status(status)
+            .body(hyper::Body::from("done"))
+            .unwrap()
+    });
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "sample", "--url", &server.url("/"), "--use"])?;
+
+    let output = quartz.cmd(&["send", "--retry", "2", "--retry-backoff", "0"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert!(output.stderr.contains("503"), "{}", output.stderr);
+    assert_eq!(output.stdout, "done");
+    assert_eq!(server.requests().len(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn it_does_not_retry_unlisted_conditions() -> TestResult {
+    let server = Server::new(|_| {
+        hyper::Response::builder()
+            .status(503)
+            .body(hyper::Body::empty())
+            .unwrap()
+    });
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "sample", "--url", &server.url("/"), "--use"])?;
+
+    let output = quartz.cmd(&[
+        "send",
+        "--retry",
+        "2",
+        "--retry-backoff",
+        "0",
+        "--retry-on",
+        "429",
+    ])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(server.requests().len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn it_times_out_slow_responses() -> TestResult {
+    let server = Server::new(|_| {
+        // Body never finishes while its sender is alive
+        let (sender, body) = hyper::Body::channel();
+        std::mem::forget(sender);
+
+        hyper::Response::new(body)
+    });
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "sample", "--url", &server.url("/"), "--use"])?;
+
+    let output = quartz.cmd(&["send", "--read-timeout", "0.2"])?;
+    assert!(!output.status.success());
+    assert!(output.stderr.contains("timed out"), "{}", output.stderr);
+
+    let output = quartz.cmd(&["send", "--max-time", "0.2"])?;
+    assert!(!output.status.success());
+    assert!(output.stderr.contains("timed out"), "{}", output.stderr);
+
+    Ok(())
+}
+
+#[test]
+fn it_rejects_durations_out_of_range() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "sample", "--url", &server.url("/"), "--use"])?;
+
+    for arg in [
+        "--max-time=1e20",
+        "--connect-timeout=-1",
+        "--retry-backoff=inf",
+    ] {
+        let output = quartz.cmd(&["send", arg])?;
+        assert!(!output.status.success());
+        assert!(
+            output.stderr.contains("invalid number of seconds"),
+            "{}",
+            output.stderr
+        );
+    }
+
+    let path = quartz.dir().join("endpoints/sample/endpoint.toml");
+    let content = std::fs::read_to_string(&path)?;
+    std::fs::write(&path, format!("{content}\n[retry]\nbackoff = inf\n"))?;
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(!output.status.success());
+    assert!(!output.stderr.contains("Duration"), "{}", output.stderr);
+    assert!(server.requests().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn it_uses_endpoint_retry_policy() -> TestResult {
+    let server = Server::new(|_| {
+        hyper::Response::builder()
+            .status(500)
+            .body(hyper::Body::empty())
+            .unwrap()
+    });
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "sample", "--url", &server.url("/"), "--use"])?;
+
+    let path = quartz.dir().join("endpoints/sample/endpoint.toml");
+    let content = std::fs::read_to_string(&path)?;
+    std::fs::write(
+        &path,
+        format!("{content}\n[retry]\ncount = 2\nbackoff = 0\n"),
+    )?;
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(server.requests().len(), 3);
+
+    Ok(())
+}
+
+#[test]
+fn it_decodes_compressed_responses() -> TestResult {
+    use quartz_cli::compression::Encoding;
+
+    let server = Server::new(|_| {
+        hyper::Response::builder()
+            .header("Content-Encoding", "gzip")
+            .body(hyper::Body::from(
+                Encoding::Gzip.encode(b"compressed").unwrap(),
+            ))
+            .unwrap()
+    });
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "sample", "--url", &server.url("/"), "--use"])?;
+
+    let output = quartz.cmd(&["send", "--compressed"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(output.stdout, "compressed");
+    assert_eq!(
+        server.requests()[0].headers()["accept-encoding"],
+        "gzip, deflate, br"
+    );
+
+    let output = quartz.cmd(&["last", "res", "body"])?;
+    assert_eq!(output.stdout.trim(), "compressed");
+
+    Ok(())
+}
+
+/// Sample endpoint answered with `status`, a gzip Content-Encoding and no body.
+fn encoded_bodiless_response(status: u16, method: &str) -> TestResult {
+    let server = Server::new(move |_| {
+        Response::builder()
+            .status(status)
+            .header("Content-Encoding", "gzip")
+            .body(Body::empty())
+            .unwrap()
+    });
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&[
+        "create",
+        "sample",
+        "-X",
+        method,
+        "--url",
+        &server.url("/"),
+        "--use",
+    ])?;
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(output.stdout, "");
+
+    let output = quartz.cmd(&["last", "res", "head"])?;
+    assert!(
+        output.stdout.contains(&status.to_string()),
+        "{}",
+        output.stdout
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_skips_decoding_empty_bodies() -> TestResult {
+    encoded_bodiless_response(200, "GET")
+}
+
+#[test]
+fn it_skips_decoding_head_responses() -> TestResult {
+    encoded_bodiless_response(200, "HEAD")
+}
+
+#[test]
+fn it_skips_decoding_no_content_responses() -> TestResult {
+    encoded_bodiless_response(204, "GET")?;
+    encoded_bodiless_response(304, "GET")
+}
+
+#[test]
+fn it_compresses_request_body() -> TestResult {
+    use quartz_cli::compression::Encoding;
+
+    let server = Server::ok();
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&[
+        "create",
+        "sample",
+        "-X",
+        "POST",
+        "--url",
+        &server.url("/"),
+        "--use",
+    ])?;
+
+    let output = quartz.cmd(&["send", "--data", "hello", "--compressed-body", "gzip"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let req = &server.requests()[0];
+    assert_eq!(req.headers()["content-encoding"], "gzip");
+    assert_eq!(Encoding::Gzip.decode(req.body())?, b"hello");
+
+    Ok(())
+}
+
+#[test]
+fn it_resolves_hosts_to_given_address() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_empty_project()?;
+    let port = server.addr().port();
+
+    let url = format!("http://api.quartz.test:{port}/");
+    quartz.cmd(&["create", "sample", "--url", &url, "--use"])?;
+
+    let resolve = format!("api.quartz.test:{port}:127.0.0.1");
+    let output = quartz.cmd(&["send", "--resolve", &resolve])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let requests = server.requests();
+    assert_eq!(
+        requests[0].headers().get("host").unwrap(),
+        &format!("api.quartz.test:{port}")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_uses_environment_resolve_entries() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_empty_project()?;
+    let port = server.addr().port();
+
+    let url = format!("http://api.quartz.test:{port}/");
+    quartz.cmd(&["create", "sample", "--url", &url, "--use"])?;
+
+    std::fs::write(
+        quartz.dir().join("env/default/env.toml"),
+        format!("resolve = [\"api.quartz.test:{port}:127.0.0.1\"]\n"),
+    )?;
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(server.requests().len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn it_rejects_malformed_resolve_entries() -> TestResult {
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "sample", "--url", "http://localhost/", "--use"])?;
+
+    let output = quartz.cmd(&["send", "--resolve", "localhost:127.0.0.1"])?;
+    assert!(!output.status.success(), "{}", output.stdout);
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn it_connects_through_unix_socket() -> TestResult {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixListener;
+
+    let quartz = Quartz::preset_empty_project()?;
+    let socket = quartz.dir().parent().unwrap().join("quartz.sock");
+    let listener = UnixListener::bind(&socket)?;
+
+    let handle = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut head = Vec::new();
+
+        for line in BufReader::new(&stream).lines() {
+            let line = line.unwrap();
+            if line.is_empty() {
+                break;
+            }
+            head.push(line);
+        }
+
+        stream
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 6\r\n\r\nsocket")
+            .unwrap();
+
+        head
+    });
+
+    quartz.cmd(&["create", "sample", "--url", "http://docker/info", "--use"])?;
+
+    let output = quartz.cmd(&["send", "--unix-socket", "quartz.sock"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(output.stdout, "socket");
+
+    let head = handle.join().unwrap();
+    assert_eq!(head[0], "GET /info HTTP/1.1");
+    assert!(head
+        .iter()
+        .any(|line| line.eq_ignore_ascii_case("host: docker")));
+
+    Ok(())
+}
+
+#[test]
+fn it_sends_many_handles_with_summary() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "users/list", "--url", &server.url("/users")])?;
+    quartz.cmd(&["create", "users/me", "--url", &server.url("/me")])?;
+
+    let output = quartz.cmd(&["send", "users/list", "users/me", "--jobs", "2"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let lines: Vec<&str> = output.stdout.lines().collect();
+    assert_eq!(lines.len(), 3, "{}", output.stdout);
+    assert!(lines[0].contains("HANDLE"), "{}", output.stdout);
+    assert!(lines[1].starts_with("users/list"), "{}", output.stdout);
+    assert!(lines[1].contains("200 OK"), "{}", output.stdout);
+    assert!(lines[2].starts_with("users/me"), "{}", output.stdout);
+
+    assert_eq!(server.requests().len(), 2);
+
+    let history = std::fs::read_dir(quartz.dir().join("user/history"))?.count();
+    assert_eq!(history, 2);
+
+    Ok(())
+}
+
+#[test]
+fn it_sends_subtree_recursively() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "users/list", "--url", &server.url("/users")])?;
+    quartz.cmd(&["create", "users/me", "--url", &server.url("/me")])?;
+    quartz.cmd(&["create", "posts", "--url", &server.url("/posts")])?;
+
+    let output = quartz.cmd(&["send", "users", "-r"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert!(output.stdout.contains("users/list"), "{}", output.stdout);
+    assert!(output.stdout.contains("users/me"), "{}", output.stdout);
+    assert!(!output.stdout.contains("posts"), "{}", output.stdout);
+
+    let mut paths: Vec<String> = server
+        .requests()
+        .iter()
+        .map(|req| req.uri().path().to_string())
+        .collect();
+    paths.sort();
+    assert_eq!(paths, vec!["/me", "/users"]);
+
+    Ok(())
+}
+
+#[test]
+fn it_fails_bulk_send_on_error_status() -> TestResult {
+    let server = Server::new(|req| {
+        let status = if req.uri().path() == "/missing" {
+            StatusCode::NOT_FOUND
+        } else {
+            StatusCode::OK
+        };
+
+        Response::builder()
+            .status(status)
+            .body(Body::empty())
+            .unwrap()
+    });
+    let quartz = Quartz::preset_empty_project()?;
+
+    quartz.cmd(&["create", "found", "--url", &server.url("/found")])?;
+    quartz.cmd(&["create", "missing", "--url", &server.url("/missing")])?;
+
+    let output = quartz.cmd(&["send", "found", "missing"])?;
+    assert!(!output.status.success(), "{}", output.stdout);
+    assert!(output.stdout.contains("404 Not Found"), "{}", output.stdout);
+
+    Ok(())
+}
+
+#[test]
+fn it_overlays_variables_from_env_files() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_using_default_env()?;
+
+    quartz.cmd(&[
+        "create",
+        "users",
+        "--url",
+        &server.url("/{{version}}/{{resource}}"),
+        "--use",
+    ])?;
+    quartz.cmd(&["var", "set", "version=v1", "resource=users"])?;
+
+    let file = quartz.dir().join("overlay.env");
+    std::fs::write(&file, "# one-off\nexport version=v2\nresource=people\n")?;
+    let file = file.to_str().unwrap();
+
+    quartz.cmd(&["send", "--env-file", file])?;
+    quartz.cmd(&["send", "--env-file", file, "-v", "resource=admins"])?;
+    quartz.cmd(&["send"])?;
+
+    let paths: Vec<String> = server
+        .requests()
+        .iter()
+        .map(|req| req.uri().path().to_string())
+        .collect();
+    assert_eq!(paths, ["/v2/people", "/v2/admins", "/v1/users"]);
+
+    Ok(())
+}
+
+#[test]
+fn it_overlays_variables_from_env_files_in_snippets() -> TestResult {
+    let quartz = Quartz::preset_using_default_env()?;
+
+    quartz.cmd(&[
+        "create",
+        "users",
+        "--url",
+        "http://localhost/{{version}}/{{resource}}",
+        "--use",
+    ])?;
+    quartz.cmd(&["var", "set", "version=v1", "resource=users"])?;
+
+    let file = quartz.dir().join("overlay.env");
+    std::fs::write(
+        &file,
+        "version=v2
+resource=people
+",
+    )?;
+    let file = file.to_str().unwrap();
+
+    let output = quartz.cmd(&[
+        "show",
+        "snippet",
+        "--env-file",
+        file,
+        "-v",
+        "resource=admins",
+        "http",
+    ])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert!(output.stdout.contains("/v2/admins"), "{}", output.stdout);
+
+    Ok(())
+}
@@ -0,0 +1,74 @@
+use hyper::{Body, Response};
+
+use crate::utils::*;
+
+fn https_project() -> Result<(Server, Quartz), Box<dyn std::error::Error>> {
+    let server = Server::https(|_| Response::new(Body::from("secure")));
+    let quartz = Quartz::preset_using_default_env()?;
+
+    quartz.cmd(&["create", "sample", "--url", &server.url("/"), "--use"])?;
+    std::fs::write(
+        quartz.dir().parent().unwrap().join("ca.pem"),
+        server.certificate(),
+    )?;
+
+    Ok((server, quartz))
+}
+
+#[test]
+fn it_rejects_unknown_certificate() -> TestResult {
+    let (_server, quartz) = https_project()?;
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(!output.status.success(), "{}", output.stdout);
+
+    Ok(())
+}
+
+#[test]
+fn it_trusts_given_ca_certificate() -> TestResult {
+    let (_server, quartz) = https_project()?;
+
+    let output = quartz.cmd(&["send", "--cacert", "ca.pem"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(output.stdout, "secure");
+
+    Ok(())
+}
+
+#[test]
+fn it_skips_verification_when_insecure() -> TestResult {
+    let (_server, quartz) = https_project()?;
+
+    let output = quartz.cmd(&["send", "-k"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(output.stdout, "secure");
+
+    Ok(())
+}
+
+#[test]
+fn it_uses_environment_tls_settings() -> TestResult {
+    let (_server, quartz) = https_project()?;
+
+    std::fs::write(
+        quartz.dir().join("env/default/env.toml"),
+        "[tls]\ncacert = \"ca.pem\"\n",
+    )?;
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(output.stdout, "secure");
+
+    Ok(())
+}
+
+#[test]
+fn it_requires_certificate_along_with_key() -> TestResult {
+    let (_server, quartz) = https_project()?;
+
+    let output = quartz.cmd(&["send", "--key", "key.pem"])?;
+    assert!(!output.status.success(), "{}", output.stdout);
+
+    Ok(())
+}
@@ -1,3 +1,5 @@
+use hyper::{Body, Response};
+
 use crate::utils::*;
 
 #[test]
@@ -141,3 +143,381 @@ fn it_can_remove_multiple_variable() -> TestResult {
 
     Ok(())
 }
+
+#[test]
+fn it_generates_builtin_variables() -> TestResult {
+    let server = Server::new(|req| {
+        let id = req.headers().get("x-request-id").unwrap().to_str().unwrap();
+        Response::new(Body::from(format!(
+            "{id}\n{}",
+            String::from_utf8_lossy(req.body())
+        )))
+    });
+    let quartz = Quartz::preset_using_default_env()?;
+
+    quartz.cmd(&[
+        "create",
+        "users/create",
+        "--url",
+        &server.url("/users"),
+        "-X",
+        "POST",
+        "-H",
+        "X-Request-Id: {{$uuid}}",
+        "--use",
+    ])?;
+    quartz.cmd_stdin(
+        &["body", "stdin"],
+        r#"{"email":"{{$randomEmail}}","age":{{$randomInt 18 20}}}"#,
+    )?;
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let (id, body) = output.stdout.split_once('\n').unwrap();
+    assert_eq!(id.len(), 36, "{id}");
+    assert!(body.contains("@example.com"), "{body}");
+    assert!(!body.contains("{{"), "{body}");
+
+    let second = quartz.cmd(&["send"])?;
+    assert_ne!(output.stdout, second.stdout);
+
+    // Sent values are the ones recorded
+    quartz.cmd(&["send"])?;
+    let last = quartz.cmd(&["last"])?;
+    let res = quartz.cmd(&["last", "res", "body"])?;
+    let (_, sent) = res.stdout.split_once('\n').unwrap();
+    assert!(last.stdout.contains(sent.trim()), "{}", last.stdout);
+
+    // Templates are kept in the endpoint
+    let show = quartz.cmd(&["body", "show"])?;
+    assert!(show.stdout.contains("{{$randomEmail}}"), "{}", show.stdout);
+
+    Ok(())
+}
+
+#[test]
+fn it_reads_process_environment_variables() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_using_default_env()?;
+
+    let url = server.url("/{{$env.QUARTZ_TEST_PATH:-fallback}}");
+    quartz.cmd(&[
+        "create",
+        "sample",
+        "--url",
+        &url,
+        "-H",
+        "X-Api-Key: {{$env.QUARTZ_TEST_KEY}}",
+        "--use",
+    ])?;
+
+    let output = quartz.cmd_env(&["send"], &[("QUARTZ_TEST_KEY", "secret")])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let output = quartz.cmd_env(
+        &["send"],
+        &[("QUARTZ_TEST_KEY", "other"), ("QUARTZ_TEST_PATH", "users")],
+    )?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let requests = server.requests();
+    assert_eq!(requests[0].uri().path(), "/fallback");
+    assert_eq!(requests[0].headers().get("x-api-key").unwrap(), "secret");
+    assert_eq!(requests[1].uri().path(), "/users");
+    assert_eq!(requests[1].headers().get("x-api-key").unwrap(), "other");
+
+    Ok(())
+}
+
+#[test]
+fn it_renders_templates_with_filters_and_nested_variables() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_using_default_env()?;
+
+    let base = server.url("");
+    quartz.cmd(&[
+        "var",
+        "set",
+        &format!("base={base}"),
+        "api={{ base }}/v{{version | default: 2}}",
+        "term=rust & go",
+    ])?;
+
+    quartz.cmd(&[
+        "create",
+        "search",
+        "--url",
+        "{{api}}/search?q={{ term | urlencode }}",
+        "-H",
+        "X-Term: {{term | upper}}",
+        "--use",
+    ])?;
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let requests = server.requests();
+    assert_eq!(requests[0].uri(), "/v2/search?q=rust%20%26%20go");
+    assert_eq!(requests[0].headers().get("x-term").unwrap(), "RUST & GO");
+
+    Ok(())
+}
+
+#[test]
+fn it_fails_on_variables_referencing_themselves() -> TestResult {
+    let quartz = Quartz::preset_using_default_env()?;
+
+    quartz.cmd(&["var", "set", "a={{b}}", "b={{a}}"])?;
+    quartz.cmd(&[
+        "create",
+        "sample",
+        "--url",
+        "http://localhost/{{a}}",
+        "--use",
+    ])?;
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(!output.status.success(), "{}", output.stdout);
+    assert!(
+        output.stderr.contains("references itself"),
+        "{}",
+        output.stderr
+    );
+
+    Ok(())
+}
+
+#[test]
+fn it_fails_on_unresolved_variables() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_using_default_env()?;
+
+    let url = server.url("/users/{{id}}");
+    quartz.cmd(&[
+        "create",
+        "sample",
+        "--url",
+        &url,
+        "-H",
+        "Authorization: Bearer {{ token }}",
+        "--use",
+    ])?;
+
+    let output = quartz.cmd(&["send"])?;
+    assert!(!output.status.success(), "{}", output.stdout);
+    assert!(output.stderr.contains("id, token"), "{}", output.stderr);
+    assert!(server.requests().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn it_hints_how_to_set_unresolved_built_in_variables() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_using_default_env()?;
+
+    let url = server.url("/{{id}}/{{$env.QUARTZ_TEST_UNSET}}/{{$nope}}");
+    quartz.cmd(&["create", "sample", "--url", &url, "--use"])?;
+
+    let output = quartz.cmd_stdin(&["send", "--prompt"], "42\nn\n")?;
+    assert!(!output.status.success(), "{}", output.stdout);
+    assert!(
+        !output.stderr.contains("unresolved variables"),
+        "{}",
+        output.stderr
+    );
+    assert!(
+        output
+            .stderr
+            .contains("unset environment variables: QUARTZ_TEST_UNSET. Export them"),
+        "{}",
+        output.stderr
+    );
+    assert!(
+        output.stderr.contains("unknown built-in variables: $nope"),
+        "{}",
+        output.stderr
+    );
+    assert!(server.requests().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn it_prompts_for_unresolved_variables() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_using_default_env()?;
+
+    let url = server.url("/users/{{id}}");
+    quartz.cmd(&["create", "sample", "--url", &url, "--use"])?;
+
+    let output = quartz.cmd_stdin(&["send", "--prompt"], "42\n")?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert_eq!(server.requests()[0].uri(), "/users/42");
+
+    Ok(())
+}
+
+#[test]
+fn it_can_set_local_variables() -> TestResult {
+    let quartz = Quartz::preset_using_default_env()?;
+
+    quartz.cmd(&["var", "set", "port=8080", "user=shared"])?;
+
+    let output = quartz.cmd(&["var", "set", "--local", "port=3000", "token=mine"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let output = quartz.cmd(&["var", "get", "port"])?;
+    assert_eq!(output.stdout.trim(), "3000");
+
+    let output = quartz.cmd(&["var", "get", "token"])?;
+    assert_eq!(output.stdout.trim(), "mine");
+
+    let shared = std::fs::read_to_string(quartz.dir().join("env/default/variables"))?;
+    assert!(!shared.contains("3000"), "{shared}");
+    assert!(!shared.contains("token"), "{shared}");
+
+    let local = std::fs::read_to_string(quartz.dir().join("user/env/default/variables"))?;
+    assert!(local.contains("port=3000"), "{local}");
+
+    // Writing shared variables keeps the overridden shared value
+    quartz.cmd(&["var", "set", "user=other"])?;
+    let shared = std::fs::read_to_string(quartz.dir().join("env/default/variables"))?;
+    assert!(shared.contains("port=8080"), "{shared}");
+    assert!(shared.contains("user=other"), "{shared}");
+
+    let output = quartz.cmd(&["var", "rm", "--local", "port"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    let output = quartz.cmd(&["var", "get", "port"])?;
+    assert_eq!(output.stdout.trim(), "8080");
+
+    Ok(())
+}
+
+#[test]
+fn it_warns_when_shared_variable_is_shadowed() -> TestResult {
+    let quartz = Quartz::preset_using_default_env()?;
+
+    quartz.cmd(&["var", "set", "port=8080"])?;
+    quartz.cmd(&["var", "set", "--local", "port=3000"])?;
+
+    let output = quartz.cmd(&["var", "set", "port=9090"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert!(
+        output
+            .stderr
+            .contains("port is shadowed by a local variable"),
+        "{}",
+        output.stderr
+    );
+
+    let output = quartz.cmd(&["var", "rm", "port"])?;
+    assert!(
+        output
+            .stderr
+            .contains("port is shadowed by a local variable"),
+        "{}",
+        output.stderr
+    );
+
+    let output = quartz.cmd(&["var", "get", "port"])?;
+    assert_eq!(output.stdout.trim(), "3000");
+
+    let output = quartz.cmd(&["var", "set", "other=1"])?;
+    assert!(output.stderr.is_empty(), "{}", output.stderr);
+
+    Ok(())
+}
+
+#[test]
+fn local_variables_override_inherited_ones() -> TestResult {
+    let quartz = Quartz::preset_using_default_env()?;
+
+    quartz.cmd(&["var", "set", "port=8080"])?;
+    quartz.cmd(&["env", "create", "dev", "--extends", "default"])?;
+    quartz.cmd(&["env", "use", "dev"])?;
+    quartz.cmd(&["var", "set", "--local", "port=3000"])?;
+
+    let output = quartz.cmd(&["var", "get", "port"])?;
+    assert_eq!(output.stdout.trim(), "3000");
+
+    quartz.cmd(&["var", "set", "name=dev"])?;
+    let shared = std::fs::read_to_string(quartz.dir().join("env/dev/variables"))?;
+    assert_eq!(shared.trim(), "name=dev");
+
+    Ok(())
+}
+
+#[test]
+fn it_inherits_scoped_variables() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_using_default_env()?;
+
+    let url = server.url("/{{api_version}}");
+    quartz.cmd(&["create", "billing/invoices", "--url", &url])?;
+    quartz.cmd(&["create", "billing/customers", "--url", &url])?;
+    quartz.cmd(&["create", "users", "--url", &url])?;
+
+    quartz.cmd(&["var", "set", "api_version=v1"])?;
+
+    let output = quartz.cmd(&["var", "set", "--scope", "billing", "api_version=v2"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    quartz.cmd(&[
+        "var",
+        "set",
+        "--scope",
+        "billing/customers",
+        "api_version=v3",
+    ])?;
+
+    let output = quartz.cmd(&["var", "get", "--scope", "billing", "api_version"])?;
+    assert_eq!(output.stdout.trim(), "v2");
+
+    for handle in ["users", "billing/invoices", "billing/customers"] {
+        let output = quartz.cmd(&["send", handle])?;
+        assert!(output.status.success(), "{}", output.stderr);
+    }
+    quartz.cmd(&["send", "billing/customers", "-v", "api_version=v4"])?;
+
+    let paths: Vec<String> = server
+        .requests()
+        .iter()
+        .map(|req| req.uri().path().to_string())
+        .collect();
+    assert_eq!(paths, ["/v1", "/v2", "/v3", "/v4"]);
+
+    let output = quartz.cmd(&["var", "rm", "--scope", "billing", "api_version"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    let output = quartz.cmd(&["var", "ls", "--scope", "billing"])?;
+    assert!(output.stdout.is_empty(), "{}", output.stdout);
+
+    Ok(())
+}
+
+#[test]
+fn local_variables_override_scoped_ones() -> TestResult {
+    let server = Server::ok();
+    let quartz = Quartz::preset_using_default_env()?;
+
+    let url = server.url("/{{api_version}}");
+    quartz.cmd(&["create", "billing/invoices", "--url", &url])?;
+
+    quartz.cmd(&["var", "set", "api_version=v1"])?;
+    quartz.cmd(&["var", "set", "--scope", "billing", "api_version=v2"])?;
+    quartz.cmd(&["var", "set", "--local", "api_version=mine"])?;
+
+    let output = quartz.cmd(&["send", "billing/invoices"])?;
+    assert!(output.status.success(), "{}", output.stderr);
+    quartz.cmd(&["send", "billing/invoices", "-v", "api_version=v4"])?;
+
+    let paths: Vec<String> = server
+        .requests()
+        .iter()
+        .map(|req| req.uri().path().to_string())
+        .collect();
+    assert_eq!(paths, ["/mine", "/v4"]);
+
+    Ok(())
+}
@@ -1,3 +1,7 @@
+mod server;
+
+pub use server::*;
+
 use cuid::cuid2;
 use std::default::Default;
 use std::ffi::OsStr;
@@ -137,10 +141,22 @@ impl Quartz {
     where
         S: AsRef<OsStr>,
     {
-        let output = Command::new(self.bin.as_path())
-            .current_dir(self.tmpdir.as_path())
+        self.cmd_env(args, &[])
+    }
+
+    /// Runs command with extra environment variables.
+    pub fn cmd_env<S>(
+        &self,
+        args: &[S],
+        envs: &[(&str, &str)],
+    ) -> Result<QuartzOutput, std::io::Error>
+    where
+        S: AsRef<OsStr>,
+    {
+        let output = self
+            .command()
             .args(args)
-            .env("NO_COLOR", "1")
+            .envs(envs.iter().copied())
             .output()?;
 
         Ok(QuartzOutput {
@@ -154,11 +170,12 @@ impl Quartz {
     where
         S: AsRef<OsStr>,
     {
-        let mut child = Command::new(self.bin.as_path())
-            .current_dir(self.tmpdir.as_path())
+        let mut child = self
+            .command()
             .args(args)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
             .spawn()?;
 
         let child_stdin = child.stdin.as_mut().unwrap();
@@ -173,6 +190,21 @@ impl Quartz {
         })
     }
 
+    /// Base command, isolated from the proxy settings of the machine running tests.
+    fn command(&self) -> Command {
+        let mut command = Command::new(self.bin.as_path());
+
+        command
+            .current_dir(self.tmpdir.as_path())
+            .env("NO_COLOR", "1");
+
+        for var in ["http_proxy", "https_proxy", "all_proxy", "no_proxy"] {
+            command.env_remove(var).env_remove(var.to_ascii_uppercase());
+        }
+
+        command
+    }
+
     pub fn dir(&self) -> PathBuf {
         self.tmpdir.join(".quartz")
     }
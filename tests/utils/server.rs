@@ -0,0 +1,248 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use hyper::body::Bytes;
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
+use hyper::{Body, Request, Response};
+use openssl::asn1::Asn1Time;
+use openssl::bn::BigNum;
+use openssl::ec::{EcGroup, EcKey};
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::pkey::PKey;
+use openssl::x509::extension::{BasicConstraints, SubjectAlternativeName};
+use openssl::x509::{X509NameBuilder, X509};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_native_tls::TlsAcceptor;
+
+type Handler = dyn Fn(&Request<Bytes>) -> Response<Body> + Send + Sync;
+
+/// Local HTTP server to send requests to. It understands both HTTP/1.1 and HTTP/2 with prior
+/// knowledge.
+pub struct Server {
+    addr: SocketAddr,
+    requests: Arc<Mutex<Vec<Request<Bytes>>>>,
+    certificate: Option<Vec<u8>>,
+    _runtime: tokio::runtime::Runtime,
+}
+
+impl Server {
+    /// Starts a server that answers every request with `handler`.
+    pub fn new<F>(handler: F) -> Self
+    where
+        F: Fn(&Request<Bytes>) -> Response<Body> + Send + Sync + 'static,
+    {
+        Self::start(Arc::new(handler), None)
+    }
+
+    /// Starts a HTTPS server that answers every request with `handler`, using a self-signed
+    /// certificate for "localhost" and "127.0.0.1".
+    pub fn https<F>(handler: F) -> Self
+    where
+        F: Fn(&Request<Bytes>) -> Response<Body> + Send + Sync + 'static,
+    {
+        Self::start(Arc::new(handler), Some(self_signed_certificate()))
+    }
+
+    /// Starts a server that answers every request with an empty 200 response.
+    pub fn ok() -> Self {
+        Self::new(|_| Response::new(Body::empty()))
+    }
+
+    fn start(handler: Arc<Handler>, tls: Option<(Vec<u8>, Vec<u8>)>) -> Self {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let listener = runtime
+            .block_on(TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0))))
+            .unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let acceptor = tls.as_ref().map(|(cert, key)| {
+            let identity = native_tls::Identity::from_pkcs8(cert, key).unwrap();
+            TlsAcceptor::from(native_tls::TlsAcceptor::new(identity).unwrap())
+        });
+
+        let recorded = requests.clone();
+        runtime.spawn(async move {
+            while let Ok((tcp, _)) = listener.accept().await {
+                let recorded = recorded.clone();
+                let handler = handler.clone();
+                let acceptor = acceptor.clone();
+
+                tokio::spawn(async move {
+                    let service = service_fn(move |req: Request<Body>| {
+                        let recorded = recorded.clone();
+                        let handler = handler.clone();
+
+                        async move {
+                            let (parts, body) = req.into_parts();
+                            let bytes = hyper::body::to_bytes(body).await.unwrap();
+                            let req = Request::from_parts(parts, bytes);
+
+                            let res = handler(&req);
+                            recorded.lock().unwrap().push(req);
+
+                            Ok::<_, Infallible>(res)
+                        }
+                    });
+
+                    match acceptor {
+                        Some(acceptor) => {
+                            if let Ok(tls) = acceptor.accept(tcp).await {
+                                let _ = Http::new().serve_connection(tls, service).await;
+                            }
+                        }
+                        None => {
+                            let _ = Http::new().serve_connection(tcp, service).await;
+                        }
+                    }
+                });
+            }
+        });
+
+        Self {
+            addr,
+            requests,
+            certificate: tls.map(|(cert, _)| cert),
+            _runtime: runtime,
+        }
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Full URL to `path` in this server.
+    pub fn url(&self, path: &str) -> String {
+        let scheme = if self.certificate.is_some() {
+            "https"
+        } else {
+            "http"
+        };
+
+        format!("{scheme}://{}{}", self.addr, path)
+    }
+
+    /// Certificate used by HTTPS servers, in PEM format.
+    pub fn certificate(&self) -> &[u8] {
+        self.certificate.as_deref().unwrap_or_default()
+    }
+
+    /// Takes all requests received so far.
+    pub fn requests(&self) -> Vec<Request<Bytes>> {
+        std::mem::take(&mut *self.requests.lock().unwrap())
+    }
+}
+
+/// Local proxy that only opens tunnels with the CONNECT method, always to `target` whatever
+/// host is asked for. Other requests are refused, as proxies do with HTTP/2 frames.
+pub struct TunnelProxy {
+    addr: SocketAddr,
+    requests: Arc<Mutex<Vec<String>>>,
+    _runtime: tokio::runtime::Runtime,
+}
+
+impl TunnelProxy {
+    pub fn new(target: SocketAddr) -> Self {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let listener = runtime
+            .block_on(TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0))))
+            .unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let recorded = requests.clone();
+        runtime.spawn(async move {
+            while let Ok((mut tcp, _)) = listener.accept().await {
+                let recorded = recorded.clone();
+
+                tokio::spawn(async move {
+                    let mut head = Vec::new();
+                    let mut buf = [0; 1024];
+                    while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+                        match tcp.read(&mut buf).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(n) => head.extend_from_slice(&buf[..n]),
+                        }
+                    }
+
+                    let head = String::from_utf8_lossy(&head).to_string();
+                    let is_connect = head.starts_with("CONNECT ");
+                    recorded.lock().unwrap().push(head);
+
+                    if !is_connect {
+                        let _ = tcp.write_all(b"HTTP/1.1 400 Bad Request\r\n\r\n").await;
+                        return;
+                    }
+
+                    let Ok(mut upstream) = TcpStream::connect(target).await else {
+                        return;
+                    };
+                    let _ = tcp.write_all(b"HTTP/1.1 200 OK\r\n\r\n").await;
+                    let _ = tokio::io::copy_bidirectional(&mut tcp, &mut upstream).await;
+                });
+            }
+        });
+
+        Self {
+            addr,
+            requests,
+            _runtime: runtime,
+        }
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Takes the heads of all requests received so far.
+    pub fn requests(&self) -> Vec<String> {
+        std::mem::take(&mut *self.requests.lock().unwrap())
+    }
+}
+
+/// Generates a certificate and its private key, both in PEM format.
+fn self_signed_certificate() -> (Vec<u8>, Vec<u8>) {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+    let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+
+    let mut name = X509NameBuilder::new().unwrap();
+    name.append_entry_by_text("CN", "localhost").unwrap();
+    let name = name.build();
+
+    let mut builder = X509::builder().unwrap();
+    builder.set_version(2).unwrap();
+    builder
+        .set_serial_number(&BigNum::from_u32(1).unwrap().to_asn1_integer().unwrap())
+        .unwrap();
+    builder.set_subject_name(&name).unwrap();
+    builder.set_issuer_name(&name).unwrap();
+    builder.set_pubkey(&key).unwrap();
+    builder
+        .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+        .unwrap();
+    builder
+        .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+        .unwrap();
+
+    let san = SubjectAlternativeName::new()
+        .dns("localhost")
+        .ip("127.0.0.1")
+        .build(&builder.x509v3_context(None, None))
+        .unwrap();
+    builder.append_extension(san).unwrap();
+    builder
+        .append_extension(BasicConstraints::new().critical().ca().build().unwrap())
+        .unwrap();
+    builder.sign(&key, MessageDigest::sha256()).unwrap();
+
+    let cert = builder.build().to_pem().unwrap();
+    let key = key.private_key_to_pem_pkcs8().unwrap();
+
+    (cert, key)
+}